@@ -20,23 +20,68 @@ pub type ChainId = (u32, Network);
 
 /// We currently support these two address types since XCM is also supporting
 /// only these ones.
-#[derive(scale::Encode, scale::Decode, Debug, PartialEq, Clone)]
+#[derive(scale::Encode, scale::Decode, Debug, Default, PartialEq, Clone)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub enum AccountType {
+	#[default]
 	AccountId32,
 	AccountKey20,
 }
 
+/// The format addresses on a chain are expected to have once decrypted.
+///
+/// Clients use this to encode addresses correctly before encrypting them.
 #[derive(scale::Encode, scale::Decode, Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub enum AddressFormat {
+	/// An SS58 address with the given network prefix.
+	Ss58 { prefix: u16 },
+	/// A 20 byte hex address, as used by EVM chains.
+	Hex20,
+}
+
+#[derive(scale::Encode, scale::Decode, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub enum Network {
 	Polkadot,
 	Kusama,
 }
 
-#[derive(scale::Encode, scale::Decode, Debug, PartialEq, Clone)]
+#[derive(scale::Encode, Debug, Default, PartialEq, Clone)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub struct ChainInfo {
 	/// We need to know the address type when making XCM transfers.
 	pub account_type: AccountType,
+	/// Whether the chain can be reached through XCM at all.
+	pub supports_xcm: bool,
+	/// The relay chain of a parachain. This is `None` for relay chains
+	/// themselves.
+	pub relay: Option<ChainId>,
+	/// Hint about the format of the addresses on the chain.
+	pub address_format: Option<AddressFormat>,
+}
+
+/// Chains stored before a field got added to `ChainInfo` are missing that
+/// field in their encoding, so it is set to its default value when the input
+/// runs out.
+impl scale::Decode for ChainInfo {
+	fn decode<I: scale::Input>(input: &mut I) -> Result<Self, scale::Error> {
+		Ok(ChainInfo {
+			account_type: AccountType::decode(input)?,
+			supports_xcm: decode_or_default(input)?,
+			relay: decode_or_default(input)?,
+			address_format: decode_or_default(input)?,
+		})
+	}
+}
+
+fn decode_or_default<T, I>(input: &mut I) -> Result<T, scale::Error>
+where
+	T: scale::Decode + Default,
+	I: scale::Input,
+{
+	match input.remaining_len()? {
+		Some(0) => Ok(T::default()),
+		_ => T::decode(input),
+	}
 }
@@ -10,9 +10,23 @@ mod tests;
 
 mod types;
 
-/// The maximum number of chars the nickname can hold.
+/// The default maximum number of chars the nickname can hold. The admin can
+/// change the limit after deployment.
 const NICKNAME_LENGTH_LIMIT: u8 = 16;
 
+/// The maximum number of identities an address book can hold.
+const ADDRESS_BOOK_SIZE_LIMIT: usize = 256;
+
+/// The maximum number of items that can be passed to a batched message.
+const BATCH_SIZE_LIMIT: usize = 64;
+
+/// The maximum number of identities that can be pinned in an address book.
+const PINNED_LIMIT: usize = 8;
+
+/// The encoded variant of the identity contract's `IdentityDoesntExist`
+/// error.
+const IDENTITY_DOESNT_EXIST_VARIANT: u8 = 1;
+
 #[derive(scale::Encode, scale::Decode, Debug, PartialEq)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum Error {
@@ -28,6 +42,20 @@ pub enum Error {
 	IdentityAlreadyAdded,
 	/// The given nickname is too long.
 	NickNameTooLong,
+	/// The caller is not allowed to perform this action.
+	NotAllowed,
+	/// The address book can't hold any more identities.
+	AddressBookFull,
+	/// The identity doesn't have an address on the given chain.
+	InvalidChain,
+	/// The identity owner doesn't allow being added to address books.
+	IdentityNotDiscoverable,
+	/// Too many items were passed to a batched message.
+	BatchSizeExceeded,
+	/// More than one identity in the address book has the given nickname.
+	AmbiguousNickname,
+	/// The address book can't have any more pinned identities.
+	TooManyPinned,
 }
 
 #[ink::contract]
@@ -52,6 +80,37 @@ mod address_book {
 		/// Address of the `Identity` contract. This is set during contract
 		/// deployment and can't be changed later.
 		pub(crate) identity_contract: AccountId,
+
+		/// The maximum number of chars a nickname can hold.
+		///
+		/// NOTE: This value is only modifiable by the admin.
+		pub(crate) nickname_length_limit: u8,
+
+		/// The admin account has the ability to update the configurable
+		/// limits of the address book contract.
+		pub(crate) admin: AccountId,
+
+		/// Whether the contract keeps count of how often each nickname is used
+		/// across all address books. Set during contract deployment since it
+		/// makes every nickname change more expensive.
+		pub(crate) track_nicknames: bool,
+
+		/// The number of identities across all address books that have a
+		/// certain nickname. Only kept up to date while `track_nicknames` is
+		/// set.
+		pub(crate) nickname_count: Mapping<Nickname, u32>,
+
+		/// The identities each user pinned to the top of their address book,
+		/// in the order they got pinned.
+		pub(crate) pinned_of: Mapping<AccountId, Vec<IdentityNo>>,
+
+		/// The identities that got removed from the identity contract. Only
+		/// known in case this contract is subscribed to identity removals.
+		pub(crate) removed_identities: Mapping<IdentityNo, bool>,
+
+		/// The position in each address book from which `prune_stale_contacts`
+		/// continues checking.
+		pub(crate) prune_cursor_of: Mapping<AccountId, u32>,
 	}
 
 	#[ink(event)]
@@ -68,6 +127,19 @@ mod address_book {
 		pub(crate) owner: AccountId,
 	}
 
+	#[ink(event)]
+	pub struct AddressBookTransferred {
+		/// The previous owner of the address book.
+		#[ink(topic)]
+		pub(crate) from: AccountId,
+		/// The new owner of the address book.
+		#[ink(topic)]
+		pub(crate) to: AccountId,
+		/// Whether the address book got merged into an existing address book
+		/// of the new owner.
+		pub(crate) merged: bool,
+	}
+
 	#[ink(event)]
 	pub struct IdentityAdded {
 		/// The owner of the address book.
@@ -88,18 +160,56 @@ mod address_book {
 		pub(crate) new_nickname: Option<Nickname>,
 	}
 
+	#[ink(event)]
+	pub struct PinnedUpdated {
+		/// The owner of the address book.
+		#[ink(topic)]
+		pub(crate) owner: AccountId,
+		/// The identity that got pinned or unpinned.
+		pub(crate) identity: IdentityNo,
+		/// Whether the identity is pinned now.
+		pub(crate) pinned: bool,
+	}
+
 	#[ink(event)]
 	pub struct IdentityRemoved {
 		pub(crate) owner: AccountId,
 		pub(crate) identity: IdentityNo,
 	}
 
+	#[ink(event)]
+	pub struct NicknameLimitChanged {
+		/// The new maximum number of chars a nickname can hold.
+		pub(crate) new_limit: u8,
+	}
+
 	impl AddressBook {
 		/// Constructor
 		/// Instantiate with the address of `Identity` contract.
 		#[ink(constructor)]
 		pub fn new(identity_contract: AccountId) -> Self {
-			AddressBook { address_book_of: Default::default(), identity_contract }
+			let caller = Self::env().caller();
+			AddressBook {
+				address_book_of: Default::default(),
+				identity_contract,
+				nickname_length_limit: NICKNAME_LENGTH_LIMIT,
+				admin: caller,
+				track_nicknames: false,
+				nickname_count: Default::default(),
+				pinned_of: Default::default(),
+				removed_identities: Default::default(),
+				prune_cursor_of: Default::default(),
+			}
+		}
+
+		/// Instantiate with the address of `Identity` contract and keep count
+		/// of how often each nickname is used across all address books.
+		#[ink(constructor)]
+		pub fn with_nickname_tracking(identity_contract: AccountId) -> Self {
+			let mut address_book = Self::new(identity_contract);
+			address_book.track_nicknames = true;
+
+			address_book
 		}
 
 		/// Returns the address of the identity contract.
@@ -108,6 +218,55 @@ mod address_book {
 			self.identity_contract
 		}
 
+		/// Returns the configuration of the address book contract.
+		#[ink(message)]
+		pub fn config(&self) -> AddressBookConfig {
+			AddressBookConfig {
+				identity_contract: self.identity_contract,
+				admin: self.admin,
+				nickname_length_limit: self.nickname_length_limit,
+				address_book_size_limit: ADDRESS_BOOK_SIZE_LIMIT as u32,
+				track_nicknames: self.track_nicknames,
+			}
+		}
+
+		/// Returns the bounds the contract enforces on its inputs.
+		#[ink(message)]
+		pub fn limits(&self) -> Limits {
+			Limits {
+				nickname_length_limit: self.nickname_length_limit,
+				address_book_size_limit: ADDRESS_BOOK_SIZE_LIMIT as u32,
+				batch_size_limit: BATCH_SIZE_LIMIT as u32,
+			}
+		}
+
+		/// Returns all the chains supported by the identity contract.
+		///
+		/// In case the identity contract can't be reached an empty list is
+		/// returned.
+		#[ink(message)]
+		pub fn available_chains(&self) -> Vec<(ChainId, ChainInfo)> {
+			[Network::Polkadot, Network::Kusama]
+				.into_iter()
+				.flat_map(|network| {
+					build_call::<DefaultEnvironment>()
+						.call(self.identity_contract)
+						.gas_limit(0)
+						.exec_input(
+							ExecutionInput::new(Selector::new(ink::selector_bytes!(
+								"available_chains"
+							)))
+							.push_arg(network.clone()),
+						)
+						.returns::<Vec<(u32, ChainInfo)>>()
+						.try_invoke()
+						.map_or(Vec::default(), |result| result.unwrap_or_default())
+						.into_iter()
+						.map(move |(id, info)| ((id, network.clone()), info))
+				})
+				.collect()
+		}
+
 		/// Creates an address book for the caller.
 		#[ink(message)]
 		pub fn create_address_book(&mut self) -> Result<(), Error> {
@@ -125,14 +284,100 @@ mod address_book {
 			Ok(())
 		}
 
+		/// Creates an address book for the caller that contains the provided
+		/// identities.
+		///
+		/// Fails without creating the address book if any of the identities
+		/// can't be added.
+		#[ink(message)]
+		pub fn create_with_identities(
+			&mut self,
+			entries: Vec<(IdentityNo, Option<Nickname>)>,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			// Only one address book per user.
+			ensure!(self.address_book_of.get(caller).is_none(), Error::AddressBookAlreadyCreated);
+
+			let mut address_book = AddressBookInfo::default();
+			for (identity_no, nickname) in entries.iter().cloned() {
+				ensure!(self.identity_exists(identity_no), Error::IdentityDoesntExist);
+				ensure!(self.is_discoverable(identity_no), Error::IdentityNotDiscoverable);
+				address_book.add_identity(identity_no, nickname, self.nickname_length_limit)?;
+			}
+
+			self.address_book_of.insert(caller, &address_book);
+			address_book
+				.identities
+				.iter()
+				.for_each(|(_, nickname)| self.count_nickname(nickname.as_ref()));
+
+			<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
+				address_book::AddressBookCreated,
+			>(self.env(), AddressBookCreated { owner: caller });
+
+			entries.into_iter().for_each(|(identity_no, _)| {
+				<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
+					address_book::IdentityAdded,
+				>(self.env(), IdentityAdded { owner: caller, identity: identity_no })
+			});
+
+			Ok(())
+		}
+
+		/// Creates an address book for the caller out of a backup made with
+		/// `export_book`.
+		///
+		/// The identities are validated the same way as in
+		/// `create_with_identities` and nothing is created in case any of them
+		/// is invalid. The pinned identities have to be part of the backup.
+		#[ink(message)]
+		pub fn import_book(&mut self, backup: AddressBookExport) -> Result<(), Error> {
+			let caller = self.env().caller();
+			let AddressBookExport { info, pinned } = backup;
+
+			ensure!(pinned.len() <= PINNED_LIMIT, Error::TooManyPinned);
+			for (index, identity_no) in pinned.iter().enumerate() {
+				ensure!(
+					info.identities.iter().any(|identity| identity.0 == *identity_no),
+					Error::IdentityNotAdded
+				);
+				ensure!(!pinned[..index].contains(identity_no), Error::IdentityAlreadyAdded);
+			}
+
+			self.create_with_identities(info.identities)?;
+
+			if !pinned.is_empty() {
+				self.pinned_of.insert(caller, &pinned);
+			}
+			pinned.into_iter().for_each(|identity_no| {
+				<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
+					address_book::PinnedUpdated,
+				>(
+					self.env(), PinnedUpdated { owner: caller, identity: identity_no, pinned: true }
+				)
+			});
+
+			Ok(())
+		}
+
 		/// Removes the address book of the caller.
 		#[ink(message)]
 		pub fn remove_address_book(&mut self) -> Result<(), Error> {
 			let caller = self.env().caller();
 
-			ensure!(self.address_book_of.get(caller).is_some(), Error::AddressBookDoesntExist);
+			let address_book = self
+				.address_book_of
+				.get(caller)
+				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
 
 			self.address_book_of.remove(caller);
+			self.pinned_of.remove(caller);
+			self.prune_cursor_of.remove(caller);
+			address_book
+				.identities
+				.iter()
+				.for_each(|(_, nickname)| self.uncount_nickname(nickname.as_ref()));
 
 			<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
 				address_book::AddressBookRemoved,
@@ -141,6 +386,75 @@ mod address_book {
 			Ok(())
 		}
 
+		/// Transfers the address book of the caller to `new_owner`.
+		///
+		/// Since an account can only own one address book the transfer fails
+		/// if `new_owner` already has one, unless `merge` is set. In that case
+		/// the identities get added to the address book of `new_owner`,
+		/// skipping the ones that are already part of it.
+		#[ink(message)]
+		pub fn transfer_address_book(
+			&mut self,
+			new_owner: AccountId,
+			merge: bool,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let address_book = self
+				.address_book_of
+				.get(caller)
+				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
+			ensure!(new_owner != caller, Error::NotAllowed);
+
+			let (address_book, merged) = match self.address_book_of.get(new_owner) {
+				Some(mut existing) => {
+					ensure!(merge, Error::AddressBookAlreadyCreated);
+					// The identities that are already part of the address book
+					// of the new owner get dropped together with their nickname.
+					let dropped: Vec<IdentityRecord> = address_book
+						.identities
+						.iter()
+						.filter(|(identity_no, _)| {
+							existing.identities.iter().any(|identity| identity.0 == *identity_no)
+						})
+						.cloned()
+						.collect();
+					existing.merge(address_book)?;
+					dropped
+						.iter()
+						.for_each(|(_, nickname)| self.uncount_nickname(nickname.as_ref()));
+					(existing, true)
+				},
+				None => (address_book, false),
+			};
+
+			self.address_book_of.remove(caller);
+			self.address_book_of.insert(new_owner, &address_book);
+			self.prune_cursor_of.remove(caller);
+
+			// When merging, the pins of the new owner take precedence.
+			let mut pinned_identities = self.pinned_of(new_owner);
+			for identity_no in self.pinned_of(caller) {
+				if pinned_identities.len() < PINNED_LIMIT &&
+					!pinned_identities.contains(&identity_no)
+				{
+					pinned_identities.push(identity_no);
+				}
+			}
+			self.pinned_of.remove(caller);
+			if !pinned_identities.is_empty() {
+				self.pinned_of.insert(new_owner, &pinned_identities);
+			}
+
+			<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
+				address_book::AddressBookTransferred,
+			>(
+				self.env(), AddressBookTransferred { from: caller, to: new_owner, merged }
+			);
+
+			Ok(())
+		}
+
 		/// Adds an identity to the user's address book.
 		#[ink(message)]
 		pub fn add_identity(
@@ -154,22 +468,12 @@ mod address_book {
 				.get(caller)
 				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
 
-			// Ensure that the provided `identity_no` is existent by calling the
-			// identity contract.
-			let identity = build_call::<DefaultEnvironment>()
-				.call(self.identity_contract)
-				.gas_limit(0)
-				.exec_input(
-					ExecutionInput::new(Selector::new(ink::selector_bytes!("identity")))
-						.push_arg(identity_no),
-				)
-				.returns::<Option<()>>()
-				.invoke();
-
-			ensure!(identity.is_some(), Error::IdentityDoesntExist);
+			ensure!(self.identity_exists(identity_no), Error::IdentityDoesntExist);
+			ensure!(self.is_discoverable(identity_no), Error::IdentityNotDiscoverable);
 
-			address_book.add_identity(identity_no, nickname)?;
+			address_book.add_identity(identity_no, nickname.clone(), self.nickname_length_limit)?;
 			self.address_book_of.insert(caller, &address_book);
+			self.count_nickname(nickname.as_ref());
 
 			<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
 				address_book::IdentityAdded,
@@ -188,8 +492,97 @@ mod address_book {
 				.get(caller)
 				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
 
+			let nickname = address_book.nickname_of(identity_no);
+			address_book.remove_identity(identity_no)?;
+			self.address_book_of.insert(caller, &address_book);
+			self.uncount_nickname(nickname.as_ref());
+			self.unpin(caller, identity_no);
+
+			<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
+				address_book::IdentityRemoved,
+			>(self.env(), IdentityRemoved { owner: caller, identity: identity_no });
+
+			Ok(())
+		}
+
+		/// Removes an identity from the user's address book in case it is part
+		/// of it.
+		///
+		/// Meant to be called after removing the identity from the identity
+		/// contract, which doesn't touch any address books. Unlike
+		/// `remove_identity` this doesn't fail if the identity isn't part of the
+		/// address book, so it can safely be called more than once.
+		#[ink(message)]
+		pub fn remove_identity_everywhere(&mut self, identity_no: IdentityNo) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let address_book: AddressBookInfo = self
+				.address_book_of
+				.get(caller)
+				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
+
+			if address_book.identities.iter().any(|identity| identity.0 == identity_no) {
+				self.remove_identity(identity_no)?;
+			}
+
+			Ok(())
+		}
+
+		/// Removes the identities from the caller's address book that got
+		/// removed from the identity contract and returns them.
+		///
+		/// Every check is a call to the identity contract, so at most
+		/// `BATCH_SIZE_LIMIT` identities are checked per call. The next call
+		/// continues where the previous one stopped and starts over from the
+		/// beginning once the end of the address book is reached.
+		#[ink(message)]
+		pub fn prune_stale_contacts(&mut self) -> Result<Vec<IdentityNo>, Error> {
+			let caller = self.env().caller();
+
+			let address_book: AddressBookInfo = self
+				.address_book_of
+				.get(caller)
+				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
+
+			let cursor = self.prune_cursor_of.get(caller).unwrap_or_default();
+			let (start, checked) = address_book.prune_batch(cursor, BATCH_SIZE_LIMIT);
+			let stale: Vec<IdentityNo> = checked
+				.iter()
+				.filter(|identity_no| !self.identity_exists(**identity_no))
+				.copied()
+				.collect();
+
+			for identity_no in stale.iter() {
+				self.remove_identity(*identity_no)?;
+			}
+
+			if let Some(next_cursor) =
+				address_book.next_prune_cursor(start, checked.len(), stale.len())
+			{
+				self.prune_cursor_of.insert(caller, &next_cursor);
+			} else {
+				self.prune_cursor_of.remove(caller);
+			}
+
+			Ok(stale)
+		}
+
+		/// Removes the identity with the given nickname from the user's
+		/// address book.
+		#[ink(message)]
+		pub fn remove_by_nickname(&mut self, nickname: Nickname) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let mut address_book: AddressBookInfo = self
+				.address_book_of
+				.get(caller)
+				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
+
+			let identity_no = address_book.identity_by_nickname(&nickname)?;
 			address_book.remove_identity(identity_no)?;
 			self.address_book_of.insert(caller, &address_book);
+			self.uncount_nickname(Some(&nickname));
+			self.unpin(caller, identity_no);
 
 			<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
 				address_book::IdentityRemoved,
@@ -211,118 +604,542 @@ mod address_book {
 				.get(caller)
 				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
 
-			address_book.update_nickname(identity_no, new_nickname.clone())?;
+			let old_nickname = address_book.nickname_of(identity_no);
+			address_book.update_nickname(
+				identity_no,
+				new_nickname.clone(),
+				self.nickname_length_limit,
+			)?;
 			self.address_book_of.insert(caller, &address_book);
+			self.uncount_nickname(old_nickname.as_ref());
+			self.count_nickname(new_nickname.as_ref());
 
 			<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
 				address_book::NicknameUpdated,
-			>(self.env(), NicknameUpdated { owner: caller, identity: identity_no, new_nickname });
+			>(
+				self.env(), NicknameUpdated { owner: caller, identity: identity_no, new_nickname }
+			);
 
 			Ok(())
 		}
 
-		/// Returns the identities stored in the address book of a user.
+		/// Updates the nicknames of multiple identities in the user's address
+		/// book.
+		///
+		/// Either all of the updates are applied or none of them.
 		#[ink(message)]
-		pub fn identities_of(&self, account: AccountId) -> Vec<IdentityRecord> {
-			self.address_book_of.get(account).unwrap_or_default().identities
+		pub fn update_nicknames(
+			&mut self,
+			updates: Vec<(IdentityNo, Option<Nickname>)>,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(updates.len() <= BATCH_SIZE_LIMIT, Error::BatchSizeExceeded);
+
+			let mut address_book = self
+				.address_book_of
+				.get(caller)
+				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
+
+			let mut old_nicknames = Vec::with_capacity(updates.len());
+			for (identity_no, new_nickname) in updates.iter().cloned() {
+				old_nicknames.push(address_book.nickname_of(identity_no));
+				address_book.update_nickname(
+					identity_no,
+					new_nickname,
+					self.nickname_length_limit,
+				)?;
+			}
+			self.address_book_of.insert(caller, &address_book);
+			for (old_nickname, (_, new_nickname)) in old_nicknames.iter().zip(updates.iter()) {
+				self.uncount_nickname(old_nickname.as_ref());
+				self.count_nickname(new_nickname.as_ref());
+			}
+
+			updates.into_iter().for_each(|(identity_no, new_nickname)| {
+				<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
+					address_book::NicknameUpdated,
+				>(
+					self.env(), NicknameUpdated { owner: caller, identity: identity_no, new_nickname }
+				)
+			});
+
+			Ok(())
 		}
 
-		/// Returns whether the user has created an address book or not
+		/// Sets the maximum number of chars a nickname can hold.
+		///
+		/// Only callable by the admin.
 		#[ink(message)]
-		pub fn has_address_book(&self) -> bool {
+		pub fn set_nickname_length_limit(&mut self, new_limit: u8) -> Result<(), Error> {
 			let caller = self.env().caller();
-			self.address_book_of.get(caller).is_some()
-		}
-	}
 
-	#[cfg(all(test, feature = "e2e-tests"))]
-	mod e2e_tests {
-		use super::*;
-		use identity::IdentityRef;
-		use ink_e2e::build_message;
+			ensure!(caller == self.admin, Error::NotAllowed);
 
-		type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+			self.nickname_length_limit = new_limit;
 
-		#[ink_e2e::test]
-		async fn constructor_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
-			let identity_constructor = IdentityRef::new();
+			<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
+				address_book::NicknameLimitChanged,
+			>(self.env(), NicknameLimitChanged { new_limit });
 
-			let identity_acc_id = client
-				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
-				.await
-				.expect("instantiate failed")
-				.account_id;
+			Ok(())
+		}
 
-			let book_constructor = AddressBookRef::new(identity_acc_id);
+		/// Returns the maximum number of chars a nickname can hold.
+		#[ink(message)]
+		pub fn nickname_length_limit(&self) -> u8 {
+			self.nickname_length_limit
+		}
 
-			let book_acc_id = client
-				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
-				.await
-				.expect("instantiate failed")
-				.account_id;
+		/// Marks an identity as removed.
+		///
+		/// Only callable by the identity contract, which calls this for every
+		/// removed identity once this contract is subscribed to removals.
+		#[ink(message)]
+		pub fn identity_removed(&mut self, identity_no: IdentityNo) -> Result<(), Error> {
+			let caller = self.env().caller();
 
-			let get_identity_contract = build_message::<AddressBookRef>(book_acc_id.clone())
-				.call(|address_book| address_book.identity_contract());
+			ensure!(caller == self.identity_contract, Error::NotAllowed);
 
-			let get_identity_contract_result = client
-				.call_dry_run(&ink_e2e::bob(), &get_identity_contract, 0, None)
-				.await
-				.return_value();
-			assert_eq!(get_identity_contract_result, identity_acc_id);
+			self.removed_identities.insert(identity_no, &true);
 
 			Ok(())
 		}
 
-		#[ink_e2e::test]
-		async fn add_identity_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
-			let identity_constructor = IdentityRef::new();
+		/// Returns whether the identity got removed from the identity
+		/// contract.
+		///
+		/// Always `false` unless this contract is subscribed to identity
+		/// removals.
+		#[ink(message)]
+		pub fn is_identity_removed(&self, identity_no: IdentityNo) -> bool {
+			self.removed_identities.get(identity_no).unwrap_or(false)
+		}
 
-			let identity_acc_id = client
-				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
-				.await
-				.expect("instantiate failed")
-				.account_id;
+		/// Returns the caller's address book together with the pinned
+		/// identities, e.g. for making a backup.
+		#[ink(message)]
+		pub fn export_book(&self) -> Result<AddressBookExport, Error> {
+			let caller = self.env().caller();
 
-			let book_constructor = AddressBookRef::new(identity_acc_id);
+			let info = self
+				.address_book_of
+				.get(caller)
+				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
 
-			let book_acc_id = client
-				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
-				.await
-				.expect("instantiate failed")
-				.account_id;
+			Ok(AddressBookExport { info, pinned: self.pinned_of(caller) })
+		}
 
-			// Alice creates an address book
-			let create_address_book_call = build_message::<AddressBookRef>(book_acc_id)
-				.call(|address_book| address_book.create_address_book());
-			client
-				.call(&ink_e2e::alice(), create_address_book_call, 0, None)
-				.await
-				.expect("failed to create an address book");
+		/// Returns the identities stored in the address book of a user.
+		///
+		/// Pinned identities come first, otherwise the identities are in the
+		/// order they got added.
+		#[ink(message)]
+		pub fn identities_of(&self, account: AccountId) -> Vec<IdentityRecord> {
+			let identities = self.address_book_of.get(account).unwrap_or_default().identities;
+			let pinned = self.pinned_of(account);
 
-			// Error: Alice tries to add an identity that hasn't been created yet.
+			let (mut records, unpinned): (Vec<IdentityRecord>, Vec<IdentityRecord>) = identities
+				.into_iter()
+				.partition(|(identity_no, _)| pinned.contains(identity_no));
+			records.extend(unpinned);
 
-			let add_identity_call = build_message::<AddressBookRef>(book_acc_id)
-				.call(|address_book| address_book.add_identity(0, Some("bob".to_string())));
-			assert_eq!(
-				client
-					.call_dry_run(&ink_e2e::alice(), &add_identity_call, 0, None)
-					.await
-					.return_value(),
-				Err(Error::IdentityDoesntExist)
+			records
+		}
+
+		/// Returns the identities the user pinned to the top of their address
+		/// book.
+		#[ink(message)]
+		pub fn pinned_of(&self, account: AccountId) -> Vec<IdentityNo> {
+			self.pinned_of.get(account).unwrap_or_default()
+		}
+
+		/// Pins an identity of the user's address book to the top of the
+		/// list, or unpins it.
+		///
+		/// At most `PINNED_LIMIT` identities can be pinned at once.
+		#[ink(message)]
+		pub fn set_pinned(&mut self, identity_no: IdentityNo, pinned: bool) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let address_book = self
+				.address_book_of
+				.get(caller)
+				.map_or(Err(Error::AddressBookDoesntExist), Ok)?;
+
+			ensure!(
+				address_book.identities.iter().any(|identity| identity.0 == identity_no),
+				Error::IdentityNotAdded
 			);
 
-			// Bob creates his identity
-			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
-				.call(|identity| identity.create_identity());
-			client
-				.call(&ink_e2e::bob(), create_identity_call, 0, None)
-				.await
-				.expect("failed to create an identity");
+			let mut pinned_identities = self.pinned_of(caller);
+			let is_pinned = pinned_identities.contains(&identity_no);
 
-			// Alice adds an identity with too long nickname
-			let add_identity_with_too_long_nickname_call =
-				build_message::<AddressBookRef>(book_acc_id).call(|address_book| {
-					address_book.add_identity(
+			if pinned && !is_pinned {
+				ensure!(pinned_identities.len() < PINNED_LIMIT, Error::TooManyPinned);
+				pinned_identities.push(identity_no);
+				self.pinned_of.insert(caller, &pinned_identities);
+			} else if !pinned && is_pinned {
+				self.unpin(caller, identity_no);
+			}
+
+			<EnvAccess<'_, DefaultEnvironment> as EmitEvent<AddressBook>>::emit_event::<
+				address_book::PinnedUpdated,
+			>(
+				self.env(), PinnedUpdated { owner: caller, identity: identity_no, pinned }
+			);
+
+			Ok(())
+		}
+
+		/// Returns whether none of the identities in the address book of
+		/// `owner` uses the given nickname.
+		#[ink(message)]
+		pub fn is_nickname_available(&self, owner: AccountId, nickname: Nickname) -> bool {
+			self.address_book_of
+				.get(owner)
+				.unwrap_or_default()
+				.identities
+				.iter()
+				.all(|(_, existing)| existing.as_ref() != Some(&nickname))
+		}
+
+		/// Returns the identity with the given nickname in the address book of
+		/// `owner`.
+		///
+		/// Returns `None` in case no identity or more than one identity has the
+		/// nickname.
+		#[ink(message)]
+		pub fn identity_no_by_nickname(
+			&self,
+			owner: AccountId,
+			nickname: Nickname,
+		) -> Option<IdentityNo> {
+			self.address_book_of.get(owner)?.identity_by_nickname(&nickname).ok()
+		}
+
+		/// Returns the number of identities across all address books that have
+		/// the given nickname.
+		///
+		/// Always zero unless the contract got instantiated with
+		/// `with_nickname_tracking`.
+		#[ink(message)]
+		pub fn global_nickname_count(&self, nickname: Nickname) -> u32 {
+			self.nickname_count.get(nickname).unwrap_or_default()
+		}
+
+		/// Returns whether the user has created an address book or not
+		#[ink(message)]
+		pub fn has_address_book(&self) -> bool {
+			let caller = self.env().caller();
+			self.address_book_of.get(caller).is_some()
+		}
+
+		/// Resolves the destination address of the identity owned by
+		/// `contact_owner` on the specified chain.
+		#[ink(message)]
+		pub fn resolve_by_owner(
+			&self,
+			contact_owner: AccountId,
+			chain: ChainId,
+		) -> Result<EncryptedAddress, Error> {
+			let identity_no = build_call::<DefaultEnvironment>()
+				.call(self.identity_contract)
+				.gas_limit(0)
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink::selector_bytes!("identity_of")))
+						.push_arg(contact_owner),
+				)
+				.returns::<Option<IdentityNo>>()
+				.invoke()
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			// The identity contract's error is decoded as its variant index
+			// since the address book doesn't depend on the identity contract.
+			build_call::<DefaultEnvironment>()
+				.call(self.identity_contract)
+				.gas_limit(0)
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink::selector_bytes!(
+						"transaction_destination"
+					)))
+					.push_arg(identity_no)
+					.push_arg(chain)
+					.push_arg(false),
+				)
+				.returns::<Result<EncryptedAddress, u8>>()
+				.invoke()
+				.map_err(|variant| match variant {
+					// The identity could have been removed in the meantime.
+					IDENTITY_DOESNT_EXIST_VARIANT => Error::IdentityDoesntExist,
+					// The only other error `transaction_destination` returns.
+					_ => Error::InvalidChain,
+				})
+		}
+
+		/// Returns the identities in the address book of `owner` together with
+		/// their nickname and their current owner in the identity contract.
+		///
+		/// Identities that got removed from the identity contract have no
+		/// owner. At most `BATCH_SIZE_LIMIT` identities are returned.
+		#[ink(message)]
+		pub fn contacts_detailed(
+			&self,
+			owner: AccountId,
+		) -> Vec<(IdentityNo, Option<Nickname>, Option<AccountId>)> {
+			self.address_book_of
+				.get(owner)
+				.unwrap_or_default()
+				.identities
+				.into_iter()
+				.take(BATCH_SIZE_LIMIT)
+				.map(|(identity_no, nickname)| (identity_no, nickname, self.owner_of(identity_no)))
+				.collect()
+		}
+
+		/// Returns whether the caller's address book contains identities that
+		/// got removed from the identity contract.
+		///
+		/// Every check is a call to the identity contract, so only the first
+		/// `BATCH_SIZE_LIMIT` identities of the address book are checked.
+		#[ink(message)]
+		pub fn has_stale_contacts(&self) -> bool {
+			let caller = self.env().caller();
+
+			self.address_book_of
+				.get(caller)
+				.unwrap_or_default()
+				.identities
+				.into_iter()
+				.take(BATCH_SIZE_LIMIT)
+				.any(|(identity_no, _)| !self.identity_exists(identity_no))
+		}
+
+		/// Returns the current owner of the identity by calling the identity
+		/// contract.
+		fn owner_of(&self, identity_no: IdentityNo) -> Option<AccountId> {
+			build_call::<DefaultEnvironment>()
+				.call(self.identity_contract)
+				.gas_limit(0)
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink::selector_bytes!("owner_of")))
+						.push_arg(identity_no),
+				)
+				.returns::<Option<AccountId>>()
+				.invoke()
+		}
+
+		/// Unpins the identity from the address book of `owner`, in case it is
+		/// pinned.
+		fn unpin(&mut self, owner: AccountId, identity_no: IdentityNo) {
+			let mut pinned_identities = self.pinned_of(owner);
+			pinned_identities.retain(|pinned| *pinned != identity_no);
+
+			if pinned_identities.is_empty() {
+				self.pinned_of.remove(owner);
+			} else {
+				self.pinned_of.insert(owner, &pinned_identities);
+			}
+		}
+
+		/// Increases the global usage count of the nickname in case nicknames
+		/// are tracked.
+		fn count_nickname(&mut self, nickname: Option<&Nickname>) {
+			let Some(nickname) = nickname.filter(|_| self.track_nicknames) else { return };
+
+			let count = self.nickname_count.get(nickname).unwrap_or_default();
+			self.nickname_count.insert(nickname, &count.saturating_add(1));
+		}
+
+		/// Decreases the global usage count of the nickname in case nicknames
+		/// are tracked.
+		fn uncount_nickname(&mut self, nickname: Option<&Nickname>) {
+			let Some(nickname) = nickname.filter(|_| self.track_nicknames) else { return };
+
+			match self.nickname_count.get(nickname).unwrap_or_default() {
+				0 | 1 => self.nickname_count.remove(nickname),
+				count => {
+					self.nickname_count.insert(nickname, &(count - 1));
+				},
+			}
+		}
+
+		/// Ensures that the provided `identity_no` is existent by calling the
+		/// identity contract.
+		fn identity_exists(&self, identity_no: IdentityNo) -> bool {
+			build_call::<DefaultEnvironment>()
+				.call(self.identity_contract)
+				.gas_limit(0)
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink::selector_bytes!("exists")))
+						.push_arg(identity_no),
+				)
+				.returns::<bool>()
+				.invoke()
+		}
+
+		/// Checks whether the owner of the identity allows it to be added to
+		/// address books by calling the identity contract.
+		fn is_discoverable(&self, identity_no: IdentityNo) -> bool {
+			build_call::<DefaultEnvironment>()
+				.call(self.identity_contract)
+				.gas_limit(0)
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink::selector_bytes!("is_discoverable")))
+						.push_arg(identity_no),
+				)
+				.returns::<bool>()
+				.invoke()
+		}
+	}
+
+	#[cfg(all(test, feature = "e2e-tests"))]
+	mod e2e_tests {
+		use super::*;
+		use identity::IdentityRef;
+		use ink_e2e::build_message;
+		use scale::Encode;
+
+		type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+		#[ink_e2e::test]
+		async fn constructor_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let get_identity_contract = build_message::<AddressBookRef>(book_acc_id.clone())
+				.call(|address_book| address_book.identity_contract());
+
+			let get_identity_contract_result = client
+				.call_dry_run(&ink_e2e::bob(), &get_identity_contract, 0, None)
+				.await
+				.return_value();
+			assert_eq!(get_identity_contract_result, identity_acc_id);
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn available_chains_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Alice adds a chain on each network to the identity contract.
+			let polkadot = ChainInfo {
+				account_type: AccountType::AccountId32,
+				supports_xcm: true,
+				..Default::default()
+			};
+			let add_polkadot_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.add_chain((0, Network::Polkadot), polkadot.clone()));
+			client
+				.call(&ink_e2e::alice(), add_polkadot_call, 0, None)
+				.await
+				.expect("failed to add a chain");
+
+			let moonriver = ChainInfo {
+				account_type: AccountType::AccountKey20,
+				supports_xcm: false,
+				..Default::default()
+			};
+			let add_moonriver_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.add_chain((2023, Network::Kusama), moonriver.clone()));
+			client
+				.call(&ink_e2e::alice(), add_moonriver_call, 0, None)
+				.await
+				.expect("failed to add a chain");
+
+			// The address book returns the same chains as the identity contract.
+			let available_chains_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.available_chains());
+
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::bob(), &available_chains_call, 0, None)
+					.await
+					.return_value(),
+				vec![((0, Network::Polkadot), polkadot), ((2023, Network::Kusama), moonriver)]
+			);
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn add_identity_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Alice creates an address book
+			let create_address_book_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.create_address_book());
+			client
+				.call(&ink_e2e::alice(), create_address_book_call, 0, None)
+				.await
+				.expect("failed to create an address book");
+
+			// Error: Alice tries to add an identity that hasn't been created yet.
+
+			let add_identity_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.add_identity(0, Some("bob".to_string())));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &add_identity_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::IdentityDoesntExist)
+			);
+
+			// Bob creates his identity
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::bob(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			// Alice adds an identity with too long nickname
+			let add_identity_with_too_long_nickname_call =
+				build_message::<AddressBookRef>(book_acc_id).call(|address_book| {
+					address_book.add_identity(
 						0, // identityNo
 						Some(
 							String::from_utf8(vec![b'a'; (NICKNAME_LENGTH_LIMIT + 1) as usize])
@@ -379,6 +1196,791 @@ mod address_book {
 			Ok(())
 		}
 
+		#[ink_e2e::test]
+		async fn create_with_identities_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Bob and Charlie create their identities.
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::bob(), create_identity_call.clone(), 0, None)
+				.await
+				.expect("failed to create an identity");
+			client
+				.call(&ink_e2e::charlie(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			// Error: Identity 2 doesn't exist so nothing gets created.
+			let invalid_create_call =
+				build_message::<AddressBookRef>(book_acc_id).call(|address_book| {
+					address_book.create_with_identities(vec![(0, None), (2, None)])
+				});
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &invalid_create_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::IdentityDoesntExist)
+			);
+
+			// Alice creates her address book containing both identities.
+			let create_call = build_message::<AddressBookRef>(book_acc_id).call(|address_book| {
+				address_book.create_with_identities(vec![(0, Some("bob".to_string())), (1, None)])
+			});
+			client
+				.call(&ink_e2e::alice(), create_call.clone(), 0, None)
+				.await
+				.expect("failed to create an address book");
+
+			let call_identities_of_alice =
+				build_message::<AddressBookRef>(book_acc_id).call(|address_book| {
+					address_book.identities_of(ink_e2e::account_id(ink_e2e::AccountKeyring::Alice))
+				});
+
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &call_identities_of_alice, 0, None)
+					.await
+					.return_value(),
+				vec![(0, Some("bob".to_string())), (1, None)]
+			);
+
+			// Error: Alice already has an address book.
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &create_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::AddressBookAlreadyCreated)
+			);
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn add_identity_respects_discoverability(
+			mut client: ink_e2e::Client<C, E>,
+		) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let create_address_book_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.create_address_book());
+			client
+				.call(&ink_e2e::alice(), create_address_book_call, 0, None)
+				.await
+				.expect("failed to create an address book");
+
+			// Bob creates his identity and opts out of being discoverable.
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::bob(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			let set_discoverable_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.set_discoverable(false));
+			client
+				.call(&ink_e2e::bob(), set_discoverable_call, 0, None)
+				.await
+				.expect("failed to set discoverable");
+
+			// Error: Alice can't add Bob's identity anymore.
+			let add_identity_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.add_identity(0, Some("bob".to_string())));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &add_identity_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::IdentityNotDiscoverable)
+			);
+
+			let create_with_identities_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.create_with_identities(vec![(0, None)]));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::charlie(), &create_with_identities_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::IdentityNotDiscoverable)
+			);
+
+			// Once Bob is discoverable again Alice can add him.
+			let set_discoverable_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.set_discoverable(true));
+			client
+				.call(&ink_e2e::bob(), set_discoverable_call, 0, None)
+				.await
+				.expect("failed to set discoverable");
+
+			client
+				.call(&ink_e2e::alice(), add_identity_call, 0, None)
+				.await
+				.expect("failed to add an identity");
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn resolve_by_owner_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let polkadot: ChainId = (0, Network::Polkadot);
+			let kusama: ChainId = (0, Network::Kusama);
+			let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+			// Error: Bob doesn't have an identity yet.
+			let resolve_polkadot_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.resolve_by_owner(bob_account, polkadot.clone()));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &resolve_polkadot_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::IdentityDoesntExist)
+			);
+
+			// Alice adds the Polkadot chain to the identity contract.
+			let add_chain_call = build_message::<IdentityRef>(identity_acc_id).call(|identity| {
+				identity.add_chain(
+					polkadot.clone(),
+					ChainInfo { account_type: AccountType::AccountId32, ..Default::default() },
+				)
+			});
+			client
+				.call(&ink_e2e::alice(), add_chain_call, 0, None)
+				.await
+				.expect("failed to add a chain");
+
+			// Bob creates his identity and adds his Polkadot address.
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::bob(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			let bob_address = bob_account.encode();
+			let add_address_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.add_address(polkadot.clone(), bob_address.clone()));
+			client
+				.call(&ink_e2e::bob(), add_address_call, 0, None)
+				.await
+				.expect("failed to add an address");
+
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &resolve_polkadot_call, 0, None)
+					.await
+					.return_value(),
+				Ok(bob_address)
+			);
+
+			// Error: Bob doesn't have an address on Kusama.
+			let resolve_kusama_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.resolve_by_owner(bob_account, kusama.clone()));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &resolve_kusama_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::InvalidChain)
+			);
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn contacts_detailed_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Bob and Charlie create their identities.
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::bob(), create_identity_call.clone(), 0, None)
+				.await
+				.expect("failed to create an identity");
+			client
+				.call(&ink_e2e::charlie(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			// Alice saves both identities in her address book.
+			let create_with_identities_call =
+				build_message::<AddressBookRef>(book_acc_id).call(|address_book| {
+					address_book.create_with_identities(vec![
+						(0, Some("bob".to_string())),
+						(1, Some("charlie".to_string())),
+					])
+				});
+			client
+				.call(&ink_e2e::alice(), create_with_identities_call, 0, None)
+				.await
+				.expect("failed to create an address book");
+
+			// Charlie removes the identity.
+			let remove_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.remove_identity());
+			client
+				.call(&ink_e2e::charlie(), remove_identity_call, 0, None)
+				.await
+				.expect("failed to remove an identity");
+
+			let alice_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+			let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+			let contacts_detailed_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.contacts_detailed(alice_account));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &contacts_detailed_call, 0, None)
+					.await
+					.return_value(),
+				vec![
+					(0, Some("bob".to_string()), Some(bob_account)),
+					(1, Some("charlie".to_string()), None),
+				]
+			);
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn global_nickname_count_tracks_added_identities(
+			mut client: ink_e2e::Client<C, E>,
+		) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::with_nickname_tracking(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Bob creates an identity.
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::bob(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			// Alice and Charlie both save it as "bob".
+			let create_address_book_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.create_address_book());
+			let add_identity_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.add_identity(0, Some("bob".to_string())));
+			for account in [ink_e2e::alice(), ink_e2e::charlie()] {
+				client
+					.call(&account, create_address_book_call.clone(), 0, None)
+					.await
+					.expect("failed to create an address book");
+				client
+					.call(&account, add_identity_call.clone(), 0, None)
+					.await
+					.expect("failed to add an identity");
+			}
+
+			let global_nickname_count_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.global_nickname_count("bob".to_string()));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &global_nickname_count_call, 0, None)
+					.await
+					.return_value(),
+				2
+			);
+
+			// Alice removes the identity again.
+			let remove_identity_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.remove_identity(0));
+			client
+				.call(&ink_e2e::alice(), remove_identity_call, 0, None)
+				.await
+				.expect("failed to remove an identity");
+
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &global_nickname_count_call, 0, None)
+					.await
+					.return_value(),
+				1
+			);
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn remove_identity_everywhere_works(
+			mut client: ink_e2e::Client<C, E>,
+		) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Alice creates an identity and adds it to Alice's own address book.
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::alice(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			let create_with_identities_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.create_with_identities(vec![(0, None)]));
+			client
+				.call(&ink_e2e::alice(), create_with_identities_call, 0, None)
+				.await
+				.expect("failed to create an address book");
+
+			// Removing the identity doesn't touch the address book.
+			let remove_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.remove_identity());
+			client
+				.call(&ink_e2e::alice(), remove_identity_call, 0, None)
+				.await
+				.expect("failed to remove an identity");
+
+			let alice_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+			let identities_of_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.identities_of(alice_account));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &identities_of_call, 0, None)
+					.await
+					.return_value(),
+				vec![(0, None)]
+			);
+
+			let remove_identity_everywhere_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.remove_identity_everywhere(0));
+			client
+				.call(&ink_e2e::alice(), remove_identity_everywhere_call, 0, None)
+				.await
+				.expect("failed to remove the identity from the address book");
+
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &identities_of_call, 0, None)
+					.await
+					.return_value(),
+				vec![]
+			);
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn has_stale_contacts_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Bob creates an identity which Alice adds to Alice's address book.
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::bob(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			let create_with_identities_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.create_with_identities(vec![(0, None)]));
+			client
+				.call(&ink_e2e::alice(), create_with_identities_call, 0, None)
+				.await
+				.expect("failed to create an address book");
+
+			let has_stale_contacts_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.has_stale_contacts());
+			assert!(!client
+				.call_dry_run(&ink_e2e::alice(), &has_stale_contacts_call, 0, None)
+				.await
+				.return_value());
+
+			// Bob removes the identity.
+			let remove_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.remove_identity());
+			client
+				.call(&ink_e2e::bob(), remove_identity_call, 0, None)
+				.await
+				.expect("failed to remove an identity");
+
+			assert!(client
+				.call_dry_run(&ink_e2e::alice(), &has_stale_contacts_call, 0, None)
+				.await
+				.return_value());
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn prune_stale_contacts_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Bob and Charlie create identities which Alice adds to Alice's
+			// address book.
+			for caller in [ink_e2e::bob(), ink_e2e::charlie()] {
+				let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+					.call(|identity| identity.create_identity());
+				client
+					.call(&caller, create_identity_call, 0, None)
+					.await
+					.expect("failed to create an identity");
+			}
+
+			let create_with_identities_call =
+				build_message::<AddressBookRef>(book_acc_id).call(|address_book| {
+					address_book.create_with_identities(vec![
+						(0, Some("bob".to_string())),
+						(1, Some("charlie".to_string())),
+					])
+				});
+			client
+				.call(&ink_e2e::alice(), create_with_identities_call, 0, None)
+				.await
+				.expect("failed to create an address book");
+
+			// Bob removes the identity.
+			let remove_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.remove_identity());
+			client
+				.call(&ink_e2e::bob(), remove_identity_call, 0, None)
+				.await
+				.expect("failed to remove an identity");
+
+			let prune_stale_contacts_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.prune_stale_contacts());
+			let result = client
+				.call(&ink_e2e::alice(), prune_stale_contacts_call.clone(), 0, None)
+				.await
+				.expect("failed to prune stale contacts");
+			assert_eq!(result.return_value(), Ok(vec![0]));
+
+			let alice_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+			let identities_of_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.identities_of(alice_account));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &identities_of_call, 0, None)
+					.await
+					.return_value(),
+				vec![(1, Some("charlie".to_string()))]
+			);
+
+			// Pruning again doesn't remove anything.
+			let result = client
+				.call(&ink_e2e::alice(), prune_stale_contacts_call, 0, None)
+				.await
+				.expect("failed to prune stale contacts");
+			assert_eq!(result.return_value(), Ok(vec![]));
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn subscribed_book_gets_notified_about_removals(
+			mut client: ink_e2e::Client<C, E>,
+		) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Alice, the admin of the identity contract, subscribes the address
+			// book to identity removals.
+			let add_subscriber_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.add_subscriber(book_acc_id));
+			client
+				.call(&ink_e2e::alice(), add_subscriber_call, 0, None)
+				.await
+				.expect("failed to add a subscriber");
+
+			// Bob creates an identity which Alice saves in an address book.
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::bob(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			let create_with_identities_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.create_with_identities(vec![(0, None)]));
+			client
+				.call(&ink_e2e::alice(), create_with_identities_call, 0, None)
+				.await
+				.expect("failed to create an address book");
+
+			let is_identity_removed_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.is_identity_removed(0));
+			assert!(!client
+				.call_dry_run(&ink_e2e::alice(), &is_identity_removed_call, 0, None)
+				.await
+				.return_value());
+
+			// Error: Only the identity contract can mark identities as removed.
+			let identity_removed_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.identity_removed(0));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &identity_removed_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::NotAllowed)
+			);
+
+			// Bob removes the identity.
+			let remove_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.remove_identity());
+			client
+				.call(&ink_e2e::bob(), remove_identity_call, 0, None)
+				.await
+				.expect("failed to remove an identity");
+
+			assert!(client
+				.call_dry_run(&ink_e2e::alice(), &is_identity_removed_call, 0, None)
+				.await
+				.return_value());
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn export_and_import_book_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+			let identity_constructor = IdentityRef::new();
+
+			let identity_acc_id = client
+				.instantiate("identity", &ink_e2e::alice(), identity_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			let book_constructor = AddressBookRef::new(identity_acc_id);
+
+			let book_acc_id = client
+				.instantiate("address-book", &ink_e2e::alice(), book_constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+
+			// Bob and Charlie create their identities which Alice saves.
+			let create_identity_call = build_message::<IdentityRef>(identity_acc_id)
+				.call(|identity| identity.create_identity());
+			client
+				.call(&ink_e2e::bob(), create_identity_call.clone(), 0, None)
+				.await
+				.expect("failed to create an identity");
+			client
+				.call(&ink_e2e::charlie(), create_identity_call, 0, None)
+				.await
+				.expect("failed to create an identity");
+
+			let create_with_identities_call =
+				build_message::<AddressBookRef>(book_acc_id).call(|address_book| {
+					address_book
+						.create_with_identities(vec![(0, Some("bob".to_string())), (1, None)])
+				});
+			client
+				.call(&ink_e2e::alice(), create_with_identities_call, 0, None)
+				.await
+				.expect("failed to create an address book");
+
+			let export_book_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.export_book());
+			let backup = client
+				.call_dry_run(&ink_e2e::alice(), &export_book_call, 0, None)
+				.await
+				.return_value()
+				.expect("failed to export the address book");
+
+			// Alice's pins are part of the backup.
+			let set_pinned_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.set_pinned(1, true));
+			client
+				.call(&ink_e2e::alice(), set_pinned_call, 0, None)
+				.await
+				.expect("failed to pin an identity");
+
+			let backup = client
+				.call_dry_run(&ink_e2e::alice(), &export_book_call, 0, None)
+				.await
+				.return_value()
+				.expect("failed to export the address book");
+			assert_eq!(backup.pinned, vec![1]);
+
+			// Error: Alice already has an address book.
+			let import_book_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.import_book(backup.clone()));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::alice(), &import_book_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::AddressBookAlreadyCreated)
+			);
+
+			// Error: Identity 2 doesn't exist, so nothing gets imported.
+			let invalid_import_call =
+				build_message::<AddressBookRef>(book_acc_id).call(|address_book| {
+					address_book.import_book(AddressBookExport {
+						info: AddressBookInfo { identities: vec![(0, None), (2, None)] },
+						pinned: vec![],
+					})
+				});
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::dave(), &invalid_import_call, 0, None)
+					.await
+					.return_value(),
+				Err(Error::IdentityDoesntExist)
+			);
+
+			// Dave imports the backup into a fresh address book.
+			client
+				.call(&ink_e2e::dave(), import_book_call, 0, None)
+				.await
+				.expect("failed to import the address book");
+
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::dave(), &export_book_call, 0, None)
+					.await
+					.return_value(),
+				Ok(backup)
+			);
+
+			// The pins got restored as well.
+			let dave_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Dave);
+			let identities_of_call = build_message::<AddressBookRef>(book_acc_id)
+				.call(|address_book| address_book.identities_of(dave_account));
+			assert_eq!(
+				client
+					.call_dry_run(&ink_e2e::dave(), &identities_of_call, 0, None)
+					.await
+					.return_value(),
+				vec![(1, None), (0, Some("bob".to_string()))]
+			);
+
+			Ok(())
+		}
+
 		#[ink_e2e::test]
 		async fn remove_identity_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
 			let identity_constructor = IdentityRef::new();
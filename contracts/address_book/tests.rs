@@ -2,7 +2,7 @@
 use crate::{address_book::*, types::*, *};
 use ink::{
 	env::{
-		test::{default_accounts, recorded_events, DefaultAccounts},
+		test::{default_accounts, recorded_events, set_caller, DefaultAccounts},
 		DefaultEnvironment,
 	},
 	primitives::AccountId,
@@ -21,6 +21,8 @@ fn constructor_works() {
 	assert!(address_book.address_book_of.get(alice).is_none());
 
 	assert_eq!(address_book.identity_contract, identity_contract);
+	assert_eq!(address_book.nickname_length_limit, NICKNAME_LENGTH_LIMIT);
+	assert_eq!(address_book.admin, alice);
 }
 
 #[ink::test]
@@ -85,6 +87,700 @@ fn has_address_book_works() {
 	assert_eq!(book.has_address_book(), true);
 }
 
+#[ink::test]
+fn set_nickname_length_limit_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(book.create_address_book(), Ok(()));
+	// Bypass the cross-contract existence check by storing the record directly.
+	book.address_book_of
+		.insert(alice, &AddressBookInfo { identities: vec![(0, Some("bob".to_string()))] });
+
+	let long_nickname =
+		String::from_utf8(vec![b'a'; (NICKNAME_LENGTH_LIMIT + 1) as usize]).unwrap();
+
+	assert_eq!(book.update_nickname(0, Some(long_nickname.clone())), Err(Error::NickNameTooLong));
+
+	// Only the admin is allowed to change the limit.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(book.set_nickname_length_limit(32), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(book.set_nickname_length_limit(32), Ok(()));
+	assert_eq!(book.nickname_length_limit(), 32);
+
+	assert_eq!(recorded_events().count(), 2);
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::NicknameLimitChanged(NicknameLimitChanged { new_limit }) = decoded_event else {
+		panic!("NicknameLimitChanged event should be emitted")
+	};
+
+	assert_eq!(new_limit, 32);
+
+	// The previously rejected nickname is now accepted.
+	assert_eq!(book.update_nickname(0, Some(long_nickname.clone())), Ok(()));
+	assert_eq!(book.identities_of(alice), vec![(0, Some(long_nickname))]);
+}
+
+#[ink::test]
+fn transfer_address_book_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(book.transfer_address_book(bob, false), Err(Error::AddressBookDoesntExist));
+
+	let alice_book = AddressBookInfo { identities: vec![(0, Some("bob".to_string())), (1, None)] };
+	book.address_book_of.insert(alice, &alice_book);
+	book.address_book_of
+		.insert(bob, &AddressBookInfo { identities: vec![(2, None)] });
+
+	// Bob already has an address book so the strict transfer fails.
+	assert_eq!(book.transfer_address_book(bob, false), Err(Error::AddressBookAlreadyCreated));
+
+	// Charlie doesn't have an address book so it is simply moved over.
+	assert_eq!(book.transfer_address_book(charlie, false), Ok(()));
+	assert_eq!(book.address_book_of.get(alice), None);
+	assert_eq!(book.address_book_of.get(charlie), Some(alice_book));
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::AddressBookTransferred(AddressBookTransferred { from, to, merged }) = decoded_event
+	else {
+		panic!("AddressBookTransferred event should be emitted")
+	};
+
+	assert_eq!(from, alice);
+	assert_eq!(to, charlie);
+	assert!(!merged);
+}
+
+#[ink::test]
+fn transfer_address_book_with_merge_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	book.address_book_of.insert(
+		alice,
+		&AddressBookInfo { identities: vec![(0, Some("bob".to_string())), (1, None)] },
+	);
+	book.address_book_of.insert(
+		bob,
+		&AddressBookInfo { identities: vec![(1, Some("charlie".to_string())), (2, None)] },
+	);
+
+	assert_eq!(book.transfer_address_book(bob, true), Ok(()));
+
+	// The identity that was already part of bob's address book is skipped.
+	assert_eq!(book.address_book_of.get(alice), None);
+	assert_eq!(
+		book.identities_of(bob),
+		vec![(1, Some("charlie".to_string())), (2, None), (0, Some("bob".to_string()))]
+	);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::AddressBookTransferred(AddressBookTransferred { from, to, merged }) = decoded_event
+	else {
+		panic!("AddressBookTransferred event should be emitted")
+	};
+
+	assert_eq!(from, alice);
+	assert_eq!(to, bob);
+	assert!(merged);
+}
+
+#[ink::test]
+fn transfer_address_book_with_merge_respects_size_limit() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	let full_book = AddressBookInfo {
+		identities: (0..ADDRESS_BOOK_SIZE_LIMIT as IdentityNo).map(|no| (no, None)).collect(),
+	};
+	book.address_book_of.insert(bob, &full_book);
+
+	// Alice's address book contains an identity that bob doesn't have.
+	let alice_book = AddressBookInfo { identities: vec![(0, None), (1000, None)] };
+	book.address_book_of.insert(alice, &alice_book);
+
+	assert_eq!(book.transfer_address_book(bob, true), Err(Error::AddressBookFull));
+	assert_eq!(book.address_book_of.get(alice), Some(alice_book));
+	assert_eq!(book.address_book_of.get(bob), Some(full_book));
+}
+
+#[ink::test]
+fn config_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(
+		book.config(),
+		AddressBookConfig {
+			identity_contract,
+			admin: alice,
+			nickname_length_limit: NICKNAME_LENGTH_LIMIT,
+			address_book_size_limit: ADDRESS_BOOK_SIZE_LIMIT as u32,
+			track_nicknames: false,
+		}
+	);
+
+	assert_eq!(book.set_nickname_length_limit(32), Ok(()));
+	assert_eq!(book.config().nickname_length_limit, 32);
+}
+
+#[ink::test]
+fn book_methods_fail_after_removal() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	// The book is inserted directly since adding identities requires the
+	// identity contract.
+	book.address_book_of
+		.insert(alice, &AddressBookInfo { identities: vec![(0, Some("bob".to_string()))] });
+	assert_eq!(book.remove_address_book(), Ok(()));
+
+	assert_eq!(book.add_identity(1, None), Err(Error::AddressBookDoesntExist));
+	assert_eq!(book.remove_identity(0), Err(Error::AddressBookDoesntExist));
+	assert_eq!(
+		book.update_nickname(0, Some("robert".to_string())),
+		Err(Error::AddressBookDoesntExist)
+	);
+	assert_eq!(book.transfer_address_book(bob, true), Err(Error::AddressBookDoesntExist));
+	assert_eq!(book.remove_address_book(), Err(Error::AddressBookDoesntExist));
+
+	assert!(!book.has_address_book());
+	assert_eq!(book.identities_of(alice), Vec::default());
+}
+
+#[ink::test]
+fn is_nickname_available_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	book.address_book_of.insert(
+		alice,
+		&AddressBookInfo { identities: vec![(0, Some("bob".to_string())), (1, None)] },
+	);
+
+	assert!(!book.is_nickname_available(alice, "bob".to_string()));
+	assert!(book.is_nickname_available(alice, "charlie".to_string()));
+
+	// Nicknames are scoped to a single address book.
+	assert!(book.is_nickname_available(bob, "bob".to_string()));
+}
+
+#[ink::test]
+fn update_nicknames_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(book.update_nicknames(vec![(0, None)]), Err(Error::AddressBookDoesntExist));
+
+	book.address_book_of.insert(
+		alice,
+		&AddressBookInfo { identities: vec![(0, Some("bob".to_string())), (1, None)] },
+	);
+
+	assert_eq!(
+		book.update_nicknames(vec![
+			(0, Some("robert".to_string())),
+			(1, Some("charlie".to_string()))
+		]),
+		Ok(())
+	);
+	assert_eq!(
+		book.identities_of(alice),
+		vec![(0, Some("robert".to_string())), (1, Some("charlie".to_string()))]
+	);
+	assert_eq!(recorded_events().count(), 2);
+
+	// Error: Identity 2 isn't part of the address book, so none of the
+	// updates are applied.
+	assert_eq!(
+		book.update_nicknames(vec![(0, None), (2, Some("django".to_string()))]),
+		Err(Error::IdentityNotAdded)
+	);
+	assert_eq!(
+		book.identities_of(alice),
+		vec![(0, Some("robert".to_string())), (1, Some("charlie".to_string()))]
+	);
+	assert_eq!(recorded_events().count(), 2);
+
+	// Error: Too many updates.
+	assert_eq!(
+		book.update_nicknames(vec![(0, None); BATCH_SIZE_LIMIT + 1]),
+		Err(Error::BatchSizeExceeded)
+	);
+}
+
+#[ink::test]
+fn limits_works() {
+	let identity_contract = get_identity_contract_address();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(
+		book.limits(),
+		Limits {
+			nickname_length_limit: NICKNAME_LENGTH_LIMIT,
+			address_book_size_limit: ADDRESS_BOOK_SIZE_LIMIT as u32,
+			batch_size_limit: BATCH_SIZE_LIMIT as u32,
+		}
+	);
+
+	// The nickname length limit follows the value set by the admin.
+	assert_eq!(book.set_nickname_length_limit(32), Ok(()));
+	assert_eq!(book.limits().nickname_length_limit, 32);
+}
+
+#[ink::test]
+fn remove_by_nickname_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	book.address_book_of.insert(
+		alice,
+		&AddressBookInfo {
+			identities: vec![
+				(0, Some("bob".to_string())),
+				(1, Some("charlie".to_string())),
+				(2, Some("charlie".to_string())),
+				(3, None),
+			],
+		},
+	);
+
+	// Error: No identity has the nickname.
+	assert_eq!(book.remove_by_nickname("django".to_string()), Err(Error::IdentityNotAdded));
+	// Error: Two identities have the nickname.
+	assert_eq!(book.remove_by_nickname("charlie".to_string()), Err(Error::AmbiguousNickname));
+
+	assert_eq!(book.remove_by_nickname("bob".to_string()), Ok(()));
+	assert_eq!(
+		book.identities_of(alice),
+		vec![(1, Some("charlie".to_string())), (2, Some("charlie".to_string())), (3, None)]
+	);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::IdentityRemoved(IdentityRemoved { owner, identity }) = decoded_event else {
+		panic!("IdentityRemoved event should be emitted")
+	};
+
+	assert_eq!(owner, alice);
+	assert_eq!(identity, 0);
+}
+
+#[ink::test]
+fn global_nickname_count_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+	let mut book = AddressBook::with_nickname_tracking(identity_contract);
+
+	assert!(book.config().track_nicknames);
+
+	book.address_book_of
+		.insert(alice, &AddressBookInfo { identities: vec![(0, None), (1, None)] });
+	book.address_book_of
+		.insert(bob, &AddressBookInfo { identities: vec![(0, None)] });
+
+	assert_eq!(book.update_nickname(0, Some("bob".to_string())), Ok(()));
+	assert_eq!(book.global_nickname_count("bob".to_string()), 1);
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(book.update_nickname(0, Some("bob".to_string())), Ok(()));
+	assert_eq!(book.global_nickname_count("bob".to_string()), 2);
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(
+		book.update_nicknames(vec![
+			(0, Some("robert".to_string())),
+			(1, Some("charlie".to_string()))
+		]),
+		Ok(())
+	);
+	assert_eq!(book.global_nickname_count("bob".to_string()), 1);
+	assert_eq!(book.global_nickname_count("robert".to_string()), 1);
+	assert_eq!(book.global_nickname_count("charlie".to_string()), 1);
+
+	assert_eq!(book.remove_identity(0), Ok(()));
+	assert_eq!(book.global_nickname_count("robert".to_string()), 0);
+
+	assert_eq!(book.remove_by_nickname("charlie".to_string()), Ok(()));
+	assert_eq!(book.global_nickname_count("charlie".to_string()), 0);
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(book.remove_address_book(), Ok(()));
+	assert_eq!(book.global_nickname_count("bob".to_string()), 0);
+}
+
+#[ink::test]
+fn global_nickname_count_is_disabled_by_default() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	book.address_book_of
+		.insert(alice, &AddressBookInfo { identities: vec![(0, None)] });
+
+	assert_eq!(book.update_nickname(0, Some("bob".to_string())), Ok(()));
+	assert_eq!(book.global_nickname_count("bob".to_string()), 0);
+}
+
+#[ink::test]
+fn identity_no_by_nickname_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(book.identity_no_by_nickname(alice, "bob".to_string()), None);
+
+	book.address_book_of.insert(
+		alice,
+		&AddressBookInfo {
+			identities: vec![
+				(0, Some("bob".to_string())),
+				(1, Some("charlie".to_string())),
+				(2, Some("charlie".to_string())),
+			],
+		},
+	);
+
+	assert_eq!(book.identity_no_by_nickname(alice, "bob".to_string()), Some(0));
+	assert_eq!(book.identity_no_by_nickname(alice, "django".to_string()), None);
+	// The nickname is ambiguous.
+	assert_eq!(book.identity_no_by_nickname(alice, "charlie".to_string()), None);
+	// Nicknames are scoped to a single address book.
+	assert_eq!(book.identity_no_by_nickname(bob, "bob".to_string()), None);
+}
+
+#[ink::test]
+fn remove_identity_everywhere_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(book.remove_identity_everywhere(0), Err(Error::AddressBookDoesntExist));
+
+	book.address_book_of.insert(
+		alice,
+		&AddressBookInfo { identities: vec![(0, Some("bob".to_string())), (1, None)] },
+	);
+
+	assert_eq!(book.remove_identity_everywhere(0), Ok(()));
+	assert_eq!(book.identities_of(alice), vec![(1, None)]);
+	assert_eq!(recorded_events().count(), 1);
+
+	// Calling it again is a no-op.
+	assert_eq!(book.remove_identity_everywhere(0), Ok(()));
+	assert_eq!(book.identities_of(alice), vec![(1, None)]);
+	assert_eq!(recorded_events().count(), 1);
+}
+
+#[ink::test]
+fn set_pinned_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(book.set_pinned(0, true), Err(Error::AddressBookDoesntExist));
+
+	book.address_book_of.insert(
+		alice,
+		&AddressBookInfo {
+			identities: vec![(0, Some("bob".to_string())), (1, None), (2, None), (3, None)],
+		},
+	);
+
+	assert_eq!(book.set_pinned(4, true), Err(Error::IdentityNotAdded));
+
+	assert_eq!(book.set_pinned(2, true), Ok(()));
+	assert_eq!(book.set_pinned(1, true), Ok(()));
+	assert_eq!(book.pinned_of(alice), vec![2, 1]);
+
+	// Pinned identities come first, both groups keep their order.
+	assert_eq!(
+		book.identities_of(alice),
+		vec![(1, None), (2, None), (0, Some("bob".to_string())), (3, None)]
+	);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::PinnedUpdated(PinnedUpdated { owner, identity, pinned }) = decoded_event else {
+		panic!("PinnedUpdated event should be emitted")
+	};
+	assert_eq!((owner, identity, pinned), (alice, 1, true));
+
+	assert_eq!(book.set_pinned(2, false), Ok(()));
+	assert_eq!(book.pinned_of(alice), vec![1]);
+	assert_eq!(
+		book.identities_of(alice),
+		vec![(1, None), (0, Some("bob".to_string())), (2, None), (3, None)]
+	);
+
+	// Removing an identity unpins it.
+	assert_eq!(book.remove_identity(1), Ok(()));
+	assert_eq!(book.pinned_of(alice), vec![]);
+}
+
+#[ink::test]
+fn set_pinned_respects_pin_limit() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	let identities = (0..=PINNED_LIMIT as IdentityNo).map(|identity_no| (identity_no, None));
+	book.address_book_of
+		.insert(alice, &AddressBookInfo { identities: identities.collect() });
+
+	for identity_no in 0..PINNED_LIMIT as IdentityNo {
+		assert_eq!(book.set_pinned(identity_no, true), Ok(()));
+	}
+	// Pinning an already pinned identity is fine.
+	assert_eq!(book.set_pinned(0, true), Ok(()));
+
+	assert_eq!(book.set_pinned(PINNED_LIMIT as IdentityNo, true), Err(Error::TooManyPinned));
+
+	assert_eq!(book.set_pinned(0, false), Ok(()));
+	assert_eq!(book.set_pinned(PINNED_LIMIT as IdentityNo, true), Ok(()));
+}
+
+#[ink::test]
+fn identity_removed_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let mut book = AddressBook::new(identity_contract);
+
+	assert!(!book.is_identity_removed(0));
+
+	// Error: Only the identity contract can mark identities as removed.
+	assert_eq!(book.identity_removed(0), Err(Error::NotAllowed));
+	assert!(!book.is_identity_removed(0));
+
+	set_caller::<DefaultEnvironment>(identity_contract);
+	assert_eq!(book.identity_removed(0), Ok(()));
+	assert!(book.is_identity_removed(0));
+	assert!(!book.is_identity_removed(1));
+}
+
+#[ink::test]
+fn export_book_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(book.export_book(), Err(Error::AddressBookDoesntExist));
+
+	let address_book = AddressBookInfo {
+		identities: vec![(0, Some("bob".to_string())), (1, None), (2, Some("charlie".to_string()))],
+	};
+	book.address_book_of.insert(alice, &address_book);
+
+	assert_eq!(
+		book.export_book(),
+		Ok(AddressBookExport { info: address_book.clone(), pinned: vec![] })
+	);
+
+	// The pins are part of the export.
+	assert_eq!(book.set_pinned(2, true), Ok(()));
+	assert_eq!(book.set_pinned(0, true), Ok(()));
+	assert_eq!(
+		book.export_book(),
+		Ok(AddressBookExport { info: address_book, pinned: vec![2, 0] })
+	);
+}
+
+#[ink::test]
+fn import_book_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	let backup = |identities: Vec<IdentityRecord>, pinned: Vec<IdentityNo>| AddressBookExport {
+		info: AddressBookInfo { identities },
+		pinned,
+	};
+
+	// Error: Only identities of the backup can be pinned.
+	assert_eq!(book.import_book(backup(vec![(0, None)], vec![1])), Err(Error::IdentityNotAdded));
+	// Error: An identity can only be pinned once.
+	assert_eq!(
+		book.import_book(backup(vec![(0, None)], vec![0, 0])),
+		Err(Error::IdentityAlreadyAdded)
+	);
+	// Error: Too many pinned identities.
+	assert_eq!(
+		book.import_book(backup(vec![], vec![0; PINNED_LIMIT + 1])),
+		Err(Error::TooManyPinned)
+	);
+	assert_eq!(book.address_book_of.get(alice), None);
+
+	assert_eq!(book.import_book(AddressBookExport::default()), Ok(()));
+	assert_eq!(book.export_book(), Ok(AddressBookExport::default()));
+
+	// Error: The caller already has an address book.
+	book.address_book_of
+		.insert(alice, &AddressBookInfo { identities: vec![(0, Some("bob".to_string()))] });
+	assert_eq!(
+		book.import_book(backup(vec![(1, None)], vec![])),
+		Err(Error::AddressBookAlreadyCreated)
+	);
+	assert_eq!(book.identities_of(alice), vec![(0, Some("bob".to_string()))]);
+}
+
+#[ink::test]
+fn prune_stale_contacts_without_identities_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	assert_eq!(book.prune_stale_contacts(), Err(Error::AddressBookDoesntExist));
+
+	// Nothing needs to be checked in an empty address book.
+	assert_eq!(book.create_address_book(), Ok(()));
+	assert_eq!(book.prune_stale_contacts(), Ok(vec![]));
+	assert_eq!(book.prune_cursor_of.get(alice), None);
+}
+
+#[ink::test]
+fn transfer_address_book_to_self_fails() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::with_nickname_tracking(identity_contract);
+
+	let alice_book = AddressBookInfo { identities: vec![(0, Some("bob".to_string())), (1, None)] };
+	book.address_book_of.insert(alice, &alice_book);
+	book.nickname_count.insert("bob".to_string(), &1);
+
+	// Error: Merging the address book into itself would drop the nickname
+	// counts of all its identities.
+	assert_eq!(book.transfer_address_book(alice, true), Err(Error::NotAllowed));
+	assert_eq!(book.transfer_address_book(alice, false), Err(Error::NotAllowed));
+
+	assert_eq!(book.address_book_of.get(alice), Some(alice_book));
+	assert_eq!(book.global_nickname_count("bob".to_string()), 1);
+}
+
+#[ink::test]
+fn create_with_identities_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	// Without any identities this behaves like `create_address_book`.
+	assert_eq!(book.create_with_identities(vec![]), Ok(()));
+	assert_eq!(book.address_book_of.get(alice), Some(AddressBookInfo::default()));
+
+	assert_eq!(recorded_events().count(), 1);
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::AddressBookCreated(AddressBookCreated { owner }) = decoded_event else {
+		panic!("AddressBookCreated event should be emitted")
+	};
+	assert_eq!(owner, alice);
+
+	// Error: Alice already has an address book.
+	assert_eq!(
+		book.create_with_identities(vec![(0, Some("bob".to_string()))]),
+		Err(Error::AddressBookAlreadyCreated)
+	);
+	assert_eq!(book.address_book_of.get(alice), Some(AddressBookInfo::default()));
+}
+
+#[ink::test]
+fn contacts_detailed_without_address_book_works() {
+	let identity_contract = get_identity_contract_address();
+
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+	let mut book = AddressBook::new(identity_contract);
+
+	// Nothing needs to be looked up for accounts without contacts.
+	assert_eq!(book.contacts_detailed(alice), vec![]);
+
+	assert_eq!(book.create_address_book(), Ok(()));
+	assert_eq!(book.contacts_detailed(alice), vec![]);
+	assert_eq!(book.contacts_detailed(bob), vec![]);
+}
+
+#[ink::test]
+fn identity_error_variant_matches_identity_contract() {
+	// `resolve_by_owner` decodes the errors of the identity contract by their
+	// variant index.
+	assert_eq!(
+		scale::Encode::encode(&identity::Error::IdentityDoesntExist),
+		vec![IDENTITY_DOESNT_EXIST_VARIANT]
+	);
+}
+
+#[ink::test]
+fn prune_cursor_works() {
+	let identities: Vec<IdentityRecord> = (0..10).map(|no| (no, None)).collect();
+	let address_book = AddressBookInfo { identities };
+
+	// The first prune starts at the beginning.
+	assert_eq!(address_book.prune_batch(0, 4), (0, vec![0, 1, 2, 3]));
+	// Subsequent prunes continue from the cursor.
+	assert_eq!(address_book.prune_batch(4, 4), (4, vec![4, 5, 6, 7]));
+	assert_eq!(address_book.prune_batch(8, 4), (8, vec![8, 9]));
+	// Pruning starts over once the cursor is past the end.
+	assert_eq!(address_book.prune_batch(10, 4), (0, vec![0, 1, 2, 3]));
+
+	// The identities that weren't removed are skipped on the next prune.
+	assert_eq!(address_book.next_prune_cursor(0, 4, 0), Some(4));
+	assert_eq!(address_book.next_prune_cursor(0, 4, 3), Some(1));
+	assert_eq!(address_book.next_prune_cursor(4, 4, 2), Some(6));
+
+	// The cursor is reset once the end was reached.
+	assert_eq!(address_book.next_prune_cursor(8, 2, 0), None);
+	assert_eq!(address_book.next_prune_cursor(8, 2, 2), None);
+	assert_eq!(address_book.next_prune_cursor(6, 4, 1), None);
+
+	assert_eq!(AddressBookInfo::default().prune_batch(3, 4), (0, vec![]));
+	assert_eq!(AddressBookInfo::default().next_prune_cursor(0, 0, 0), None);
+}
+
 fn get_default_accounts() -> DefaultAccounts<DefaultEnvironment> {
 	default_accounts::<DefaultEnvironment>()
 }
@@ -1,8 +1,11 @@
 //! Types used in the address book contract.
 
-use ink::prelude::{string::String, vec::Vec};
 #[cfg(feature = "std")]
 use ink::storage::traits::StorageLayout;
+use ink::{
+	prelude::{string::String, vec::Vec},
+	primitives::AccountId,
+};
 
 use crate::*;
 
@@ -20,19 +23,60 @@ pub struct AddressBookInfo {
 	pub(crate) identities: Vec<IdentityRecord>,
 }
 
+/// A backup of an address book as returned by `export_book`.
+#[derive(scale::Encode, scale::Decode, Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct AddressBookExport {
+	/// The identities of the address book together with their nicknames.
+	pub(crate) info: AddressBookInfo,
+	/// The pinned identities, in the order they got pinned.
+	pub(crate) pinned: Vec<IdentityNo>,
+}
+
+/// The configuration of the address book contract.
+#[derive(scale::Encode, scale::Decode, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct AddressBookConfig {
+	/// Address of the `Identity` contract.
+	pub identity_contract: AccountId,
+	/// The admin of the address book contract.
+	pub admin: AccountId,
+	/// The maximum number of chars a nickname can hold.
+	pub nickname_length_limit: u8,
+	/// The maximum number of identities an address book can hold.
+	pub address_book_size_limit: u32,
+	/// Whether the contract keeps count of how often each nickname is used.
+	pub track_nicknames: bool,
+}
+
+/// The bounds the address book contract enforces on its inputs.
+#[derive(scale::Encode, scale::Decode, Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Limits {
+	/// The maximum number of chars a nickname can hold.
+	pub nickname_length_limit: u8,
+	/// The maximum number of identities an address book can hold.
+	pub address_book_size_limit: u32,
+	/// The maximum number of items that can be passed to a batched message.
+	pub batch_size_limit: u32,
+}
+
 impl AddressBookInfo {
 	pub fn add_identity(
 		&mut self,
 		identity_no: IdentityNo,
 		nickname: Option<Nickname>,
+		nickname_length_limit: u8,
 	) -> Result<(), Error> {
 		ensure!(
 			!self.identities.iter().any(|identity| identity.0 == identity_no),
 			Error::IdentityAlreadyAdded
 		);
 
+		ensure!(self.identities.len() < ADDRESS_BOOK_SIZE_LIMIT, Error::AddressBookFull);
+
 		if let Some(name) = nickname.clone() {
-			ensure!(name.len() <= NICKNAME_LENGTH_LIMIT as usize, Error::NickNameTooLong);
+			ensure!(name.len() <= nickname_length_limit as usize, Error::NickNameTooLong);
 		}
 
 		self.identities.push((identity_no, nickname));
@@ -53,13 +97,36 @@ impl AddressBookInfo {
 		Ok(())
 	}
 
+	/// Returns the nickname of the given identity.
+	pub fn nickname_of(&self, identity_no: IdentityNo) -> Option<Nickname> {
+		self.identities
+			.iter()
+			.find(|(existing, _)| *existing == identity_no)
+			.and_then(|(_, nickname)| nickname.clone())
+	}
+
+	/// Returns the identity that has the given nickname.
+	pub fn identity_by_nickname(&self, nickname: &Nickname) -> Result<IdentityNo, Error> {
+		let mut matches = self
+			.identities
+			.iter()
+			.filter(|(_, existing)| existing.as_ref() == Some(nickname))
+			.map(|(identity_no, _)| *identity_no);
+
+		let identity_no = matches.next().map_or(Err(Error::IdentityNotAdded), Ok)?;
+		ensure!(matches.next().is_none(), Error::AmbiguousNickname);
+
+		Ok(identity_no)
+	}
+
 	pub fn update_nickname(
 		&mut self,
 		identity_no: IdentityNo,
 		new_nickname: Option<Nickname>,
+		nickname_length_limit: u8,
 	) -> Result<(), Error> {
 		if let Some(name) = new_nickname.clone() {
-			ensure!(name.len() <= NICKNAME_LENGTH_LIMIT as usize, Error::NickNameTooLong);
+			ensure!(name.len() <= nickname_length_limit as usize, Error::NickNameTooLong);
 		}
 
 		let index = self
@@ -72,4 +139,56 @@ impl AddressBookInfo {
 
 		Ok(())
 	}
+
+	/// Adds all the identities of `other` which aren't part of this address
+	/// book yet.
+	pub fn merge(&mut self, other: AddressBookInfo) -> Result<(), Error> {
+		let new_identities: Vec<IdentityRecord> = other
+			.identities
+			.into_iter()
+			.filter(|(identity_no, _)| {
+				!self.identities.iter().any(|identity| identity.0 == *identity_no)
+			})
+			.collect();
+
+		ensure!(
+			self.identities.len() + new_identities.len() <= ADDRESS_BOOK_SIZE_LIMIT,
+			Error::AddressBookFull
+		);
+
+		self.identities.extend(new_identities);
+
+		Ok(())
+	}
+
+	/// Returns the position from which pruning continues given the `cursor`
+	/// of the previous prune, together with the next `limit` identities
+	/// that need to be checked. Starts over from the beginning once the
+	/// cursor is past the end.
+	pub fn prune_batch(&self, cursor: u32, limit: usize) -> (usize, Vec<IdentityNo>) {
+		let start = cursor as usize;
+		let start = if start < self.identities.len() { start } else { 0 };
+
+		let batch = self
+			.identities
+			.iter()
+			.skip(start)
+			.take(limit)
+			.map(|(identity_no, _)| *identity_no)
+			.collect();
+
+		(start, batch)
+	}
+
+	/// Returns the cursor of the next prune after `checked` identities got
+	/// checked from `start` on and `removed` of them got removed, or `None`
+	/// in case the end of the address book was reached.
+	///
+	/// Needs to be called on the address book as it was before pruning.
+	pub fn next_prune_cursor(&self, start: usize, checked: usize, removed: usize) -> Option<u32> {
+		// The identities that weren't removed are skipped on the next call.
+		let next_cursor = start + checked - removed;
+
+		(next_cursor < self.identities.len() - removed).then_some(next_cursor as u32)
+	}
 }
@@ -15,6 +15,32 @@ pub use self::identity::{Identity, IdentityRef};
 /// Encrypted addresses should never exceed this size limit.
 const ADDRESS_SIZE_LIMIT: usize = 128;
 
+/// The maximum number of items that can be passed to a batched message.
+const BATCH_SIZE_LIMIT: usize = 64;
+
+/// The maximum number of items a single query returns.
+const QUERY_RESULT_LIMIT: usize = 128;
+
+/// Proofs of address control should never exceed this size limit.
+const PROOF_SIZE_LIMIT: usize = 128;
+
+/// Memos should never exceed this size limit.
+const MEMO_SIZE_LIMIT: usize = 64;
+
+/// The maximum number of contracts that can subscribe to identity removals.
+const SUBSCRIBER_LIMIT: usize = 8;
+
+/// The number of blocks within which a key rotation can be rolled back.
+const ROTATION_ROLLBACK_WINDOW: types::BlockNumber = 100;
+
+/// The number of buckets returned by `address_count_histogram`. The last
+/// bucket also counts all identities with more addresses.
+const HISTOGRAM_BUCKETS: usize = 16;
+
+/// The number of addresses an identity can have unless configured otherwise
+/// by an admin.
+const DEFAULT_MAX_ADDRESSES_PER_IDENTITY: u32 = 200;
+
 /// All the possible errors that may occur when interacting with the identity
 /// contract.
 #[derive(scale::Encode, scale::Decode, Debug, PartialEq)]
@@ -27,6 +53,33 @@ pub enum Error {
 	AddressSizeExceeded,
 	ChainNameTooLong,
 	AlreadyIdentityOwner,
+	BatchSizeExceeded,
+	AlreadyAdmin,
+	NotAdmin,
+	CannotRemoveLastAdmin,
+	DuplicateAddressAcrossChains,
+	ProofSizeExceeded,
+	NotAllowlisted,
+	MemoTooLong,
+	RecoveryRequired,
+	AddressMismatch,
+	ChainRemovalNotScheduled,
+	ChainRemovalTimelocked,
+	InvalidClaim,
+	NoStagedAddresses,
+	RecoveryIsOwner,
+	TooManyAddresses,
+	RecoveryMismatch,
+	IdentityLocked,
+	InvalidSuccessor,
+	ChainCountMismatch,
+	DuplicateChain,
+	AlreadySubscribed,
+	NotSubscribed,
+	TooManySubscribers,
+	NoRotation,
+	RotationExpired,
+	ChainHasParachains,
 }
 
 #[ink::contract]
@@ -34,7 +87,15 @@ mod identity {
 	use super::*;
 	use crate::types::*;
 	use common::types::{ChainInfo, *};
-	use ink::storage::Mapping;
+	use ink::{
+		env::{
+			call::{build_call, ExecutionInput, Selector},
+			hash::Blake2x256,
+			DefaultEnvironment,
+		},
+		storage::Mapping,
+	};
+	use scale::Encode;
 
 	/// Storage
 	#[ink(storage)]
@@ -64,21 +125,126 @@ mod identity {
 		/// The chain information associated with a specific `ChainId` on the
 		/// specific network.
 		///
-		/// NOTE: This mapping is only modifiable by the admin.
+		/// NOTE: This mapping is only modifiable by the admins.
 		pub(crate) chain_info_of: Mapping<ChainId, ChainInfo>,
 
 		/// Keeps track of the `ChainId`s for all the chains that are inside the
 		/// `chain_info_of` mapping.
 		///
-		/// NOTE: This mapping is only modifiable by the admin.
+		/// NOTE: This mapping is only modifiable by the admins.
 		pub(crate) chain_ids: Vec<ChainId>,
 
-		/// The admin account has the ability to update the list of supported
+		/// The number of identities that have an address on a specific chain.
+		pub(crate) chain_usage: Mapping<ChainId, u32>,
+
+		/// The admin accounts have the ability to update the list of supported
 		/// chains that can be used in Dotflow.
 		///
 		/// In the future it could be a good idea to have this controlled by
 		/// governance.
-		pub(crate) admin: AccountId,
+		pub(crate) admins: Mapping<AccountId, bool>,
+
+		/// The number of accounts inside the `admins` mapping. Used to ensure
+		/// that the last admin can't be removed.
+		pub(crate) admin_count: u32,
+
+		/// Whether an identity can be added to address books. Identities are
+		/// discoverable unless their owner opted out.
+		pub(crate) discoverable: Mapping<IdentityNo, bool>,
+
+		/// The total number of addresses stored across all identities.
+		pub(crate) total_addresses: u64,
+
+		/// Owner-submitted proofs that the owner controls the address of the
+		/// identity on a specific chain. The proofs are stored as opaque blobs
+		/// and verified off-chain.
+		pub(crate) proof_of: Mapping<(IdentityNo, ChainId), AddressProof>,
+
+		/// While set, only accounts inside the `allowlist` can create an
+		/// identity. Used for permissioned launches.
+		pub(crate) gated: bool,
+
+		/// The accounts that are allowed to create an identity while the
+		/// contract is `gated`.
+		pub(crate) allowlist: Mapping<AccountId, bool>,
+
+		/// The block at which an identity got created.
+		pub(crate) created_at: Mapping<IdentityNo, BlockNumber>,
+
+		/// An account the identity owner designated for apps to reference,
+		/// e.g. a hot wallet used for payouts. This is metadata only and grants
+		/// no permissions over the identity.
+		pub(crate) operational_account_of: Mapping<IdentityNo, AccountId>,
+
+		/// Optional memos stored alongside addresses, e.g. the destination tag
+		/// an exchange requires. Kept separate from `IdentityInfo` so that the
+		/// encoding of existing identities is unaffected.
+		pub(crate) memo_of: Mapping<(IdentityNo, ChainId), Memo>,
+
+		/// While set, identities need a recovery account before they can add
+		/// addresses.
+		pub(crate) require_recovery: bool,
+
+		/// Whether an identity opted into being listed in the public
+		/// directory. Identities are not listed unless their owner opted in.
+		pub(crate) directory_listed: Mapping<IdentityNo, bool>,
+
+		/// The number of blocks that need to pass between scheduling the
+		/// removal of a chain and executing it. While this is zero chains can
+		/// also be removed right away.
+		pub(crate) chain_removal_delay: BlockNumber,
+
+		/// The block from which on the scheduled removal of a chain can be
+		/// executed.
+		pub(crate) pending_chain_removal: Mapping<ChainId, BlockNumber>,
+
+		/// The hash of the claim code of identities that got registered
+		/// without an owner. Whoever knows the preimage can claim the identity.
+		pub(crate) claim_code_hash_of: Mapping<IdentityNo, [u8; 32]>,
+
+		/// Addresses that got staged when the identity was transferred. The
+		/// new owner can replace the addresses of the identity with them.
+		pub(crate) staged_addresses_of: Mapping<IdentityNo, IdentityInfo>,
+
+		/// The sequence number of the latest event emitted for an identity.
+		///
+		/// Each event emitted for an identity carries the next number in its
+		/// `seq`, which allows indexers to detect missed events. Events
+		/// replayed by `emit_snapshot` are the exception as they carry the
+		/// current number.
+		pub(crate) seq_of: Mapping<IdentityNo, u32>,
+
+		/// The maximum number of addresses a single identity can have.
+		pub(crate) max_addresses_per_identity: u32,
+
+		/// The account that created each identity. Unlike `owner_of` this is
+		/// never changed by ownership transfers.
+		pub(crate) creator_of: Mapping<IdentityNo, AccountId>,
+
+		/// The block until which an identity can't be transferred, edited or
+		/// removed.
+		pub(crate) locked_until: Mapping<IdentityNo, BlockNumber>,
+
+		/// The chain on which the owner of an identity prefers to receive
+		/// transfers. Only a public hint for senders, it doesn't affect
+		/// routing.
+		pub(crate) receiving_preference_of: Mapping<IdentityNo, ChainId>,
+
+		/// The identity that senders can be forwarded to in case an identity
+		/// doesn't have an address on a chain.
+		pub(crate) successor_of: Mapping<IdentityNo, IdentityNo>,
+
+		/// The contracts that get notified whenever an identity is removed,
+		/// e.g. address book contracts.
+		pub(crate) subscribers: Vec<AccountId>,
+
+		/// The key rotations each account can still roll back. Cleared once
+		/// the identity moves on or the rotation gets finalized.
+		pub(crate) rotation_of: Mapping<AccountId, KeyRotation>,
+
+		/// The account each identity with a pending key rotation got rotated
+		/// away from.
+		pub(crate) rotated_from: Mapping<IdentityNo, AccountId>,
 	}
 
 	/// Events
@@ -89,6 +255,53 @@ mod identity {
 		pub(crate) owner: AccountId,
 		/// The `IdentityNo` associated with the created identity.
 		pub(crate) identity_no: IdentityNo,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct ClaimableIdentityRegistered {
+		/// The `IdentityNo` of the identity that can be claimed.
+		pub(crate) identity_no: IdentityNo,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct IdentityClaimed {
+		/// The `IdentityNo` of the claimed identity.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// The account that claimed the identity.
+		#[ink(topic)]
+		pub(crate) owner: AccountId,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct OwnershipTransferred {
+		/// The `IdentityNo` of the identity that changed its owner.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// The previous owner of the identity.
+		pub(crate) previous_owner: AccountId,
+		/// The new owner of the identity.
+		#[ink(topic)]
+		pub(crate) new_owner: AccountId,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct LockedUntilSet {
+		/// The `IdentityNo` of the identity that got locked.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// The block until which the identity is locked.
+		pub(crate) locked_until: BlockNumber,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
 	}
 
 	#[ink(event)]
@@ -100,6 +313,8 @@ mod identity {
 		pub(crate) chain: ChainId,
 		/// The newly added address.
 		pub(crate) address: EncryptedAddress,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
 	}
 
 	#[ink(event)]
@@ -111,6 +326,8 @@ mod identity {
 		pub(crate) chain: ChainId,
 		/// The updated address value.
 		pub(crate) updated_address: EncryptedAddress,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
 	}
 
 	#[ink(event)]
@@ -120,6 +337,8 @@ mod identity {
 		pub(crate) identity_no: IdentityNo,
 		/// The chain on which the address has been removed.
 		pub(crate) chain: ChainId,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
 	}
 
 	#[ink(event)]
@@ -127,6 +346,8 @@ mod identity {
 		/// The `IdentityNo` of the identity that got removed.
 		#[ink(topic)]
 		pub(crate) identity_no: IdentityNo,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
 	}
 
 	#[ink(event)]
@@ -136,6 +357,8 @@ mod identity {
 		pub(crate) chain_id: ChainId,
 		/// The address type used on the chain.
 		pub(crate) account_type: AccountType,
+		/// Whether the chain can be reached through XCM.
+		pub(crate) supports_xcm: bool,
 	}
 
 	#[ink(event)]
@@ -145,6 +368,10 @@ mod identity {
 		pub(crate) chain_id: ChainId,
 		/// The address type used on the updated chain.
 		pub(crate) account_type: AccountType,
+		/// Whether the updated chain can be reached through XCM.
+		pub(crate) supports_xcm: bool,
+		/// The relay chain of the updated chain.
+		pub(crate) relay: Option<ChainId>,
 	}
 
 	#[ink(event)]
@@ -155,13 +382,138 @@ mod identity {
 		pub(crate) chain_id: ChainId,
 	}
 
+	#[ink(event)]
+	pub struct ChainRemovalScheduled {
+		/// The `ChainId` of the chain that is scheduled for removal.
+		#[ink(topic)]
+		pub(crate) chain_id: ChainId,
+		/// The block from which on the removal can be executed.
+		pub(crate) executable_at: BlockNumber,
+	}
+
+	#[ink(event)]
+	pub struct ChainRemovalCancelled {
+		/// The `ChainId` of the chain that is no longer scheduled for removal.
+		#[ink(topic)]
+		pub(crate) chain_id: ChainId,
+	}
+
 	#[ink(event)]
 	pub struct RecoveryAccountSet {
 		/// The `IdentityNo` of the identity that set a recovery account.
 		#[ink(topic)]
 		pub(crate) identity_no: IdentityNo,
 		/// The newly set recovery account.
+		#[ink(topic)]
+		pub(crate) recovery_account: AccountId,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct RecoveryUsed {
+		/// The `IdentityNo` of the identity that got recovered.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// The recovery account that transferred the ownership.
+		#[ink(topic)]
 		pub(crate) recovery_account: AccountId,
+		/// The new owner of the identity.
+		pub(crate) new_owner: AccountId,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct DirectoryListingSet {
+		/// The `IdentityNo` of the identity that changed its listing.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// Whether the identity is listed in the public directory.
+		pub(crate) listed: bool,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct ReceivingPreferenceSet {
+		/// The `IdentityNo` of the identity that changed its receiving
+		/// preference.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// The preferred chain, `None` in case the preference got cleared.
+		pub(crate) chain: Option<ChainId>,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct SuccessorSet {
+		/// The `IdentityNo` of the identity that changed its successor.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// The new successor, `None` in case it got cleared.
+		pub(crate) successor: Option<IdentityNo>,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct RotationFinalized {
+		/// The `IdentityNo` of the identity whose key rotation can no longer
+		/// be rolled back.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct OperationalAccountSet {
+		/// The `IdentityNo` of the identity that changed its operational
+		/// account.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// The new operational account, `None` in case it got cleared.
+		pub(crate) operational_account: Option<AccountId>,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct ProofSet {
+		/// The `IdentityNo` of the identity that set a proof.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// The chain of the address the proof is for.
+		pub(crate) chain: ChainId,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct DiscoverableSet {
+		/// The `IdentityNo` of the identity that changed its discoverability.
+		#[ink(topic)]
+		pub(crate) identity_no: IdentityNo,
+		/// Whether the identity can be added to address books.
+		pub(crate) discoverable: bool,
+		/// The sequence number of the event, see `seq_of`.
+		pub(crate) seq: u32,
+	}
+
+	#[ink(event)]
+	pub struct AdminAdded {
+		/// The newly added admin account.
+		#[ink(topic)]
+		pub(crate) admin: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct AdminRemoved {
+		/// The removed admin account.
+		#[ink(topic)]
+		pub(crate) admin: AccountId,
 	}
 
 	impl Default for Identity {
@@ -174,6 +526,9 @@ mod identity {
 		#[ink(constructor)]
 		pub fn new() -> Self {
 			let caller = Self::env().caller();
+			let mut admins = Mapping::default();
+			admins.insert(caller, &true);
+
 			Self {
 				number_to_identity: Default::default(),
 				owner_of: Default::default(),
@@ -181,8 +536,33 @@ mod identity {
 				latest_identity_no: 0,
 				chain_info_of: Default::default(),
 				chain_ids: Default::default(),
+				chain_usage: Default::default(),
 				recovery_account_of: Default::default(),
-				admin: caller,
+				admins,
+				admin_count: 1,
+				discoverable: Default::default(),
+				total_addresses: 0,
+				proof_of: Default::default(),
+				gated: false,
+				allowlist: Default::default(),
+				created_at: Default::default(),
+				operational_account_of: Default::default(),
+				memo_of: Default::default(),
+				require_recovery: false,
+				directory_listed: Default::default(),
+				chain_removal_delay: 0,
+				pending_chain_removal: Default::default(),
+				claim_code_hash_of: Default::default(),
+				staged_addresses_of: Default::default(),
+				seq_of: Default::default(),
+				max_addresses_per_identity: DEFAULT_MAX_ADDRESSES_PER_IDENTITY,
+				creator_of: Default::default(),
+				locked_until: Default::default(),
+				receiving_preference_of: Default::default(),
+				successor_of: Default::default(),
+				subscribers: Default::default(),
+				rotation_of: Default::default(),
+				rotated_from: Default::default(),
 			}
 		}
 
@@ -190,13 +570,10 @@ mod identity {
 		pub fn init_with_chains(chains: Vec<ChainInfo>, chain_ids: Vec<ChainId>) -> Self {
 			let mut chain_info_of = Mapping::default();
 
-			assert!(
-				chains.len() == chain_ids.len(),
-				"Each chain needs to have a corresponding chain id specified."
-			);
+			if let Err(error) = Self::validate_chain_set(&chains, &chain_ids) {
+				panic!("Invalid chain set: {:?}", error)
+			}
 
-			// Iterate over all the chains provided and make sure that no
-			// fields are exceeding the length limits.
 			chain_ids
 				.clone()
 				.into_iter()
@@ -206,6 +583,9 @@ mod identity {
 				});
 
 			let caller = Self::env().caller();
+			let mut admins = Mapping::default();
+			admins.insert(caller, &true);
+
 			Self {
 				number_to_identity: Default::default(),
 				owner_of: Default::default(),
@@ -213,8 +593,78 @@ mod identity {
 				latest_identity_no: 0,
 				chain_info_of,
 				chain_ids,
+				chain_usage: Default::default(),
 				recovery_account_of: Default::default(),
-				admin: caller,
+				admins,
+				admin_count: 1,
+				discoverable: Default::default(),
+				total_addresses: 0,
+				proof_of: Default::default(),
+				gated: false,
+				allowlist: Default::default(),
+				created_at: Default::default(),
+				operational_account_of: Default::default(),
+				memo_of: Default::default(),
+				require_recovery: false,
+				directory_listed: Default::default(),
+				chain_removal_delay: 0,
+				pending_chain_removal: Default::default(),
+				claim_code_hash_of: Default::default(),
+				staged_addresses_of: Default::default(),
+				seq_of: Default::default(),
+				max_addresses_per_identity: DEFAULT_MAX_ADDRESSES_PER_IDENTITY,
+				creator_of: Default::default(),
+				locked_until: Default::default(),
+				receiving_preference_of: Default::default(),
+				successor_of: Default::default(),
+				subscribers: Default::default(),
+				rotation_of: Default::default(),
+				rotated_from: Default::default(),
+			}
+		}
+
+		/// Checks that every chain has a corresponding chain id, that no chain
+		/// id is listed twice and that the relay chain of each chain is part
+		/// of the set and isn't the chain itself.
+		pub(crate) fn validate_chain_set(
+			chains: &[ChainInfo],
+			chain_ids: &[ChainId],
+		) -> Result<(), Error> {
+			ensure!(chains.len() == chain_ids.len(), Error::ChainCountMismatch);
+
+			for (index, (chain_id, chain)) in chain_ids.iter().zip(chains.iter()).enumerate() {
+				ensure!(!chain_ids[..index].contains(chain_id), Error::DuplicateChain);
+
+				if let Some(relay) = chain.relay.as_ref() {
+					ensure!(relay != chain_id && chain_ids.contains(relay), Error::InvalidChain);
+				}
+			}
+
+			Ok(())
+		}
+
+		/// Returns the bounds the contract enforces on its inputs.
+		#[ink(message)]
+		pub fn limits(&self) -> Limits {
+			Limits {
+				address_size_limit: ADDRESS_SIZE_LIMIT as u32,
+				proof_size_limit: PROOF_SIZE_LIMIT as u32,
+				memo_size_limit: MEMO_SIZE_LIMIT as u32,
+				batch_size_limit: BATCH_SIZE_LIMIT as u32,
+				query_result_limit: QUERY_RESULT_LIMIT as u32,
+				max_addresses_per_identity: self.max_addresses_per_identity,
+			}
+		}
+
+		/// Returns the values of the contract that can be tuned by an admin.
+		#[ink(message)]
+		pub fn admin_config(&self) -> AdminConfig {
+			AdminConfig {
+				address_size_limit: ADDRESS_SIZE_LIMIT as u32,
+				max_addresses_per_identity: self.max_addresses_per_identity,
+				require_recovery: self.require_recovery,
+				gated: self.gated,
+				chain_removal_delay: self.chain_removal_delay,
 			}
 		}
 
@@ -225,6 +675,58 @@ mod identity {
 			self.number_to_identity.get(identity_no)
 		}
 
+		/// Returns whether an identity with the provided `IdentityNo` exists.
+		#[ink(message)]
+		pub fn exists(&self, identity_no: IdentityNo) -> bool {
+			self.number_to_identity.contains(identity_no)
+		}
+
+		/// Returns an estimate of the storage an identity consumes, i.e. the
+		/// size of its encoded `IdentityInfo` in bytes.
+		#[ink(message)]
+		pub fn storage_estimate(&self, identity_no: IdentityNo) -> Result<u32, Error> {
+			let identity_info = self
+				.number_to_identity
+				.get(identity_no)
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			Ok(identity_info.encoded_size() as u32)
+		}
+
+		/// Returns the sequence number of the latest event emitted for the
+		/// identity.
+		#[ink(message)]
+		pub fn seq_of(&self, identity_no: IdentityNo) -> u32 {
+			self.seq_of.get(identity_no).unwrap_or_default()
+		}
+
+		/// Returns the account that created an identity, regardless of any
+		/// later ownership transfers. For claimable identities this is the
+		/// account that claimed it.
+		#[ink(message)]
+		pub fn creator_of(&self, identity_no: IdentityNo) -> Option<AccountId> {
+			self.creator_of.get(identity_no)
+		}
+
+		/// Returns the block until which an identity is locked.
+		#[ink(message)]
+		pub fn locked_until(&self, identity_no: IdentityNo) -> Option<BlockNumber> {
+			self.locked_until.get(identity_no)
+		}
+
+		/// Returns the chain on which the owner of an identity prefers to
+		/// receive transfers.
+		#[ink(message)]
+		pub fn receiving_preference_of(&self, identity_no: IdentityNo) -> Option<ChainId> {
+			self.receiving_preference_of.get(identity_no)
+		}
+
+		/// Returns the successor of an identity.
+		#[ink(message)]
+		pub fn successor_of(&self, identity_no: IdentityNo) -> Option<IdentityNo> {
+			self.successor_of.get(identity_no)
+		}
+
 		/// Returns the owner of an identity.
 		#[ink(message)]
 		pub fn owner_of(&self, identity_no: IdentityNo) -> Option<AccountId> {
@@ -237,254 +739,2047 @@ mod identity {
 			self.identity_of.get(owner)
 		}
 
+		/// Returns the operational account of an identity.
+		#[ink(message)]
+		pub fn operational_account_of(&self, identity_no: IdentityNo) -> Option<AccountId> {
+			self.operational_account_of.get(identity_no)
+		}
+
+		/// Returns whether the identity can be added to address books.
+		#[ink(message)]
+		pub fn is_discoverable(&self, identity_no: IdentityNo) -> bool {
+			self.discoverable.get(identity_no).unwrap_or(true)
+		}
+
+		/// Returns whether `account` is the current owner of the identity.
+		///
+		/// dApps can use this to confirm on-chain that the signer of an
+		/// off-chain challenge owns the identity.
+		#[ink(message)]
+		pub fn current_owner_is(&self, identity_no: IdentityNo, account: AccountId) -> bool {
+			self.owner_of.get(identity_no) == Some(account)
+		}
+
+		/// Returns whether `account` is allowed to modify the identity.
+		///
+		/// The recovery account is not taken into account since it can only
+		/// transfer the ownership of the identity.
+		#[ink(message)]
+		pub fn can_edit(&self, identity_no: IdentityNo, account: AccountId) -> bool {
+			self.owner_of.get(identity_no) == Some(account)
+		}
+
+		/// Returns the total number of addresses stored across all identities.
+		#[ink(message)]
+		pub fn total_addresses(&self) -> u64 {
+			self.total_addresses
+		}
+
 		/// Returns the chain name that is associated with the specified `ChainId`.
 		#[ink(message)]
 		pub fn chain_info_of(&self, chain_id: ChainId) -> Option<ChainInfo> {
 			self.chain_info_of.get(chain_id)
 		}
 
+		/// Returns for each of the given chains whether it is supported.
+		///
+		/// At most `BATCH_SIZE_LIMIT` chains can be checked at once.
+		#[ink(message)]
+		pub fn chains_supported(
+			&self,
+			chain_ids: Vec<ChainId>,
+		) -> Result<Vec<(ChainId, bool)>, Error> {
+			ensure!(chain_ids.len() <= BATCH_SIZE_LIMIT, Error::BatchSizeExceeded);
+
+			Ok(chain_ids
+				.into_iter()
+				.map(|chain_id| {
+					let supported = self.chain_info_of.contains(chain_id.clone());
+					(chain_id, supported)
+				})
+				.collect())
+		}
+
+		/// Returns the addresses of an identity within `start..start + limit`,
+		/// ordered by chain.
+		///
+		/// `limit` is capped to `QUERY_RESULT_LIMIT`.
+		#[ink(message)]
+		pub fn addresses_of_paged(
+			&self,
+			identity_no: IdentityNo,
+			start: u32,
+			limit: u32,
+		) -> Result<Vec<(ChainId, EncryptedAddress)>, Error> {
+			let identity_info = self
+				.number_to_identity
+				.get(identity_no)
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			Ok(identity_info
+				.addresses
+				.into_iter()
+				.skip(start as usize)
+				.take((limit as usize).min(QUERY_RESULT_LIMIT))
+				.collect())
+		}
+
+		/// Returns whether `count` more addresses can be added to the identity
+		/// without exceeding `max_addresses_per_identity`.
+		///
+		/// Meant for planning batches, the addresses themselves aren't
+		/// validated.
+		#[ink(message)]
+		pub fn can_batch_add_addresses(
+			&self,
+			identity_no: IdentityNo,
+			count: u32,
+		) -> Result<bool, Error> {
+			let identity_info = self
+				.number_to_identity
+				.get(identity_no)
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			Ok((identity_info.addresses.len() as u64).saturating_add(count as u64) <=
+				self.max_addresses_per_identity as u64)
+		}
+
+		/// Returns the addresses of an identity together with the `ChainInfo`
+		/// of their chain.
+		///
+		/// Addresses on chains that are no longer supported are skipped.
+		#[ink(message)]
+		pub fn addresses_with_info(
+			&self,
+			identity_no: IdentityNo,
+		) -> Result<Vec<(ChainId, ChainInfo, EncryptedAddress)>, Error> {
+			let identity_info = self
+				.number_to_identity
+				.get(identity_no)
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			Ok(identity_info
+				.addresses
+				.into_iter()
+				.filter_map(|(chain, address)| {
+					let info = self.chain_info_of.get(chain.clone())?;
+					Some((chain, info, address))
+				})
+				.collect())
+		}
+
+		/// Returns the addresses of an identity packed into a single byte
+		/// vector, meant for off-chain tools that parse the bytes directly.
+		///
+		/// The addresses are concatenated in the order they are stored. Each
+		/// entry is laid out as:
+		/// - the SCALE encoded `ChainId`: the chain id as a little endian `u32` followed by the
+		///   index of its `Network` as a single byte,
+		/// - the length of the address as a little endian `u32`,
+		/// - the bytes of the encrypted address.
+		#[ink(message)]
+		pub fn addresses_packed(&self, identity_no: IdentityNo) -> Result<Vec<u8>, Error> {
+			let identity_info = self
+				.number_to_identity
+				.get(identity_no)
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			let mut packed = Vec::new();
+			for (chain, address) in identity_info.addresses {
+				chain.encode_to(&mut packed);
+				(address.len() as u32).encode_to(&mut packed);
+				packed.extend(address);
+			}
+
+			Ok(packed)
+		}
+
+		/// Returns whether the chain uses 20 byte EVM style addresses, or `None`
+		/// in case the chain isn't supported.
+		#[ink(message)]
+		pub fn is_evm_chain(&self, chain_id: ChainId) -> Option<bool> {
+			self.chain_info_of
+				.get(chain_id)
+				.map(|info| info.account_type == AccountType::AccountKey20)
+		}
+
 		/// Returns the destination address of a transaction that needs to be
 		/// routed to the specified identity on the specified chain.
+		///
+		/// In case the receiver doesn't have an address on the chain and
+		/// `follow_successor` is set, the address of the receiver's successor
+		/// is returned instead. Only a single hop is followed, so the successor
+		/// of the successor is never considered.
 		#[ink(message)]
 		pub fn transaction_destination(
 			&self,
 			receiver: IdentityNo,
 			chain: ChainId,
+			follow_successor: bool,
 		) -> Result<EncryptedAddress, Error> {
 			let receiver_identity = self
 				.number_to_identity
 				.get(receiver)
 				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
 
-			match receiver_identity.addresses.into_iter().find(|(id, _)| *id == chain) {
-				Some((_, address)) => Ok(address),
-				None => Err(Error::InvalidChain),
+			if let Some(address) = receiver_identity.address_of(&chain) {
+				return Ok(address.clone())
 			}
+
+			self.successor_of
+				.get(receiver)
+				.filter(|_| follow_successor)
+				.and_then(|successor| self.number_to_identity.get(successor))
+				.and_then(|successor_identity| successor_identity.address_of(&chain).cloned())
+				.map_or(Err(Error::InvalidChain), Ok)
 		}
 
-		/// A list of all the available chains each associated with the associated
-		/// `ChainId`.
+		/// Returns the destination addresses on the specified chain for each
+		/// of the receivers.
+		///
+		/// Each receiver gets its own result so that a single invalid receiver
+		/// doesn't fail the whole query. At most `BATCH_SIZE_LIMIT` receivers
+		/// can be resolved at once.
 		#[ink(message)]
-		pub fn available_chains(&self, network: Network) -> Vec<(u32, ChainInfo)> {
-			self.chain_ids
-				.clone()
+		pub fn destinations_on_chain(
+			&self,
+			receivers: Vec<IdentityNo>,
+			chain: ChainId,
+		) -> Result<Vec<Destination>, Error> {
+			ensure!(receivers.len() <= BATCH_SIZE_LIMIT, Error::BatchSizeExceeded);
+
+			Ok(receivers
 				.into_iter()
-				.map(|id| (id.clone(), self.chain_info_of(id)))
-				.filter(|(id, _)| id.1 == network)
-				.filter_map(|(id, maybe_chain)| maybe_chain.map(|info| (id.0, info)))
-				.collect()
+				.map(|receiver| {
+					(receiver, self.transaction_destination(receiver, chain.clone(), false))
+				})
+				.collect())
 		}
 
-		/// Creates an identity and returns the `IdentityNo`.
-		///
-		/// A user can only create one identity.
+		/// Returns the address of the caller's own identity on the specified
+		/// chain.
 		#[ink(message)]
-		pub fn create_identity(&mut self) -> Result<IdentityNo, Error> {
+		pub fn my_destination(&self, chain: ChainId) -> Result<EncryptedAddress, Error> {
 			let caller = self.env().caller();
 
-			ensure!(self.identity_of.get(caller).is_none(), Error::AlreadyIdentityOwner);
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
 
-			let identity_no = self.latest_identity_no;
+			self.transaction_destination(identity_no, chain, false)
+		}
 
-			let new_identity: IdentityInfo = Default::default();
+		/// Returns the chains out of the provided ones on which the identity
+		/// doesn't have an address yet.
+		#[ink(message)]
+		pub fn missing_chains(
+			&self,
+			identity_no: IdentityNo,
+			chains: Vec<ChainId>,
+		) -> Result<Vec<ChainId>, Error> {
+			ensure!(chains.len() <= BATCH_SIZE_LIMIT, Error::BatchSizeExceeded);
+
+			let identity_info = self
+				.number_to_identity
+				.get(identity_no)
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			Ok(chains
+				.into_iter()
+				.filter(|chain| identity_info.address_of(chain).is_none())
+				.collect())
+		}
+
+		/// Returns the chains on which both identities have an address.
+		#[ink(message)]
+		pub fn sendable_chains(
+			&self,
+			from: IdentityNo,
+			to: IdentityNo,
+		) -> Result<Vec<ChainId>, Error> {
+			let sender =
+				self.number_to_identity.get(from).map_or(Err(Error::IdentityDoesntExist), Ok)?;
+			let receiver =
+				self.number_to_identity.get(to).map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			Ok(sender
+				.addresses
+				.into_iter()
+				.map(|(chain, _)| chain)
+				.filter(|chain| receiver.address_of(chain).is_some())
+				.collect())
+		}
+
+		/// Returns the addresses of the identity that are on chains of the
+		/// specified network.
+		#[ink(message)]
+		pub fn addresses_on_network(
+			&self,
+			identity_no: IdentityNo,
+			network: Network,
+		) -> Result<Vec<(ChainId, EncryptedAddress)>, Error> {
+			let identity_info = self
+				.number_to_identity
+				.get(identity_no)
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			Ok(identity_info
+				.addresses
+				.into_iter()
+				.filter(|((_, chain_network), _)| *chain_network == network)
+				.collect())
+		}
+
+		/// Returns up to `top_n` of the most used chains on which the identity
+		/// doesn't have an address yet, most used first.
+		///
+		/// Chains no identity has an address on are never suggested. `top_n`
+		/// is capped to `QUERY_RESULT_LIMIT`.
+		#[ink(message)]
+		pub fn suggested_chains(
+			&self,
+			identity_no: IdentityNo,
+			top_n: u32,
+		) -> Result<Vec<ChainId>, Error> {
+			let identity_info = self
+				.number_to_identity
+				.get(identity_no)
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			let mut candidates: Vec<(ChainId, u32)> = self
+				.chain_ids
+				.iter()
+				.filter(|chain| identity_info.address_of(chain).is_none())
+				.map(|chain| (chain.clone(), self.chain_usage.get(chain).unwrap_or_default()))
+				.filter(|(_, usage)| *usage > 0)
+				.collect();
+
+			candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+			Ok(candidates
+				.into_iter()
+				.take((top_n as usize).min(QUERY_RESULT_LIMIT))
+				.map(|(chain, _)| chain)
+				.collect())
+		}
+
+		/// Returns the chains on which the identity has an address but which
+		/// are no longer part of the supported chains.
+		#[ink(message)]
+		pub fn stale_chains_of(&self, identity_no: IdentityNo) -> Result<Vec<ChainId>, Error> {
+			let identity_info = self
+				.number_to_identity
+				.get(identity_no)
+				.map_or(Err(Error::IdentityDoesntExist), Ok)?;
+
+			Ok(identity_info
+				.addresses
+				.into_iter()
+				.map(|(chain, _)| chain)
+				.filter(|chain| !self.chain_info_of.contains(chain))
+				.collect())
+		}
+
+		/// Returns whether all the addresses of the identity are on supported
+		/// chains. Identities that aren't clean can be fixed with
+		/// `prune_stale_addresses`.
+		#[ink(message)]
+		pub fn is_identity_clean(&self, identity_no: IdentityNo) -> Result<bool, Error> {
+			Ok(self.stale_chains_of(identity_no)?.is_empty())
+		}
+
+		/// A list of all the available chains each associated with the associated
+		/// `ChainId`.
+		#[ink(message)]
+		pub fn available_chains(&self, network: Network) -> Vec<(u32, ChainInfo)> {
+			self.chain_ids
+				.clone()
+				.into_iter()
+				.map(|id| (id.clone(), self.chain_info_of(id)))
+				.filter(|(id, _)| id.1 == network)
+				.filter_map(|(id, maybe_chain)| maybe_chain.map(|info| (id.0, info)))
+				.collect()
+		}
+
+		/// A list of all the available chains that can be reached through XCM
+		/// each associated with the associated `ChainId`.
+		#[ink(message)]
+		pub fn xcm_chains(&self, network: Network) -> Vec<(u32, ChainInfo)> {
+			self.available_chains(network)
+				.into_iter()
+				.filter(|(_, info)| info.supports_xcm)
+				.collect()
+		}
+
+		/// Returns all the chains that have the specified chain set as their
+		/// relay chain.
+		#[ink(message)]
+		pub fn chains_under_relay(&self, relay_id: ChainId) -> Vec<ChainId> {
+			self.chain_ids
+				.iter()
+				.filter(|id| {
+					self.chain_info_of.get(*id).and_then(|info| info.relay) ==
+						Some(relay_id.clone())
+				})
+				.take(QUERY_RESULT_LIMIT)
+				.cloned()
+				.collect()
+		}
+
+		/// A list of all the available chains together with the number of
+		/// identities that have an address on each of them.
+		#[ink(message)]
+		pub fn available_chains_with_usage(&self) -> Vec<(ChainId, ChainInfo, u32)> {
+			self.chain_ids
+				.iter()
+				.filter_map(|id| {
+					self.chain_info_of(id.clone()).map(|info| {
+						(id.clone(), info, self.chain_usage.get(id).unwrap_or_default())
+					})
+				})
+				.take(QUERY_RESULT_LIMIT)
+				.collect()
+		}
+
+		/// Returns for each network the total number of addresses stored on
+		/// the supported chains of the network.
+		#[ink(message)]
+		pub fn network_usage(&self) -> Vec<(Network, u32)> {
+			[Network::Polkadot, Network::Kusama]
+				.into_iter()
+				.map(|network| {
+					let usage = self
+						.chain_ids
+						.iter()
+						.filter(|(_, chain_network)| *chain_network == network)
+						.map(|chain| self.chain_usage.get(chain).unwrap_or_default())
+						.fold(0u32, |total, usage| total.saturating_add(usage));
+					(network, usage)
+				})
+				.collect()
+		}
+
+		/// Returns how many identities within `start..end` have a given number
+		/// of addresses.
+		///
+		/// The value at index `i` is the number of identities with exactly `i`
+		/// addresses, except for the last bucket which also counts identities
+		/// with more addresses. At most `QUERY_RESULT_LIMIT` identity numbers
+		/// are scanned per call, so large ranges have to be queried in chunks.
+		#[ink(message)]
+		pub fn address_count_histogram(&self, start: IdentityNo, end: IdentityNo) -> Vec<u32> {
+			let mut buckets = Vec::from([0u32; HISTOGRAM_BUCKETS]);
+
+			(start..end.min(self.latest_identity_no))
+				.take(QUERY_RESULT_LIMIT)
+				.filter_map(|identity_no| self.number_to_identity.get(identity_no))
+				.for_each(|identity| {
+					let bucket = identity.addresses.len().min(HISTOGRAM_BUCKETS - 1);
+					buckets[bucket] += 1;
+				});
+
+			buckets
+		}
+
+		/// Returns the owners of the identities within `start..end`.
+		///
+		/// At most `QUERY_RESULT_LIMIT` identity numbers are scanned per call,
+		/// so large ranges have to be queried in chunks.
+		#[ink(message)]
+		pub fn owners_in_range(&self, start: IdentityNo, end: IdentityNo) -> Vec<AccountId> {
+			let mut owners: Vec<AccountId> = (start..end.min(self.latest_identity_no))
+				.take(QUERY_RESULT_LIMIT)
+				.filter_map(|identity_no| self.owner_of(identity_no))
+				.collect();
+
+			owners.sort();
+			owners.dedup();
+
+			owners
+		}
+
+		/// Returns the identities within `start..end` that have no recovery
+		/// account set.
+		///
+		/// Meant as an indexing aid. At most `QUERY_RESULT_LIMIT` identity
+		/// numbers are scanned per call, so large ranges have to be queried in
+		/// chunks.
+		#[ink(message)]
+		pub fn identities_without_recovery(
+			&self,
+			start: IdentityNo,
+			end: IdentityNo,
+		) -> Vec<IdentityNo> {
+			(start..end.min(self.latest_identity_no))
+				.take(QUERY_RESULT_LIMIT)
+				.filter(|identity_no| self.number_to_identity.contains(identity_no))
+				.filter(|identity_no| !self.recovery_account_of.contains(identity_no))
+				.collect()
+		}
+
+		/// Returns the identities within `start..end` that have `recovery` set
+		/// as their recovery account.
+		///
+		/// At most `QUERY_RESULT_LIMIT` identity numbers are scanned per call,
+		/// so large ranges have to be queried in chunks.
+		#[ink(message)]
+		pub fn identities_with_recovery(
+			&self,
+			recovery: AccountId,
+			start: IdentityNo,
+			end: IdentityNo,
+		) -> Vec<IdentityNo> {
+			(start..end.min(self.latest_identity_no))
+				.take(QUERY_RESULT_LIMIT)
+				.filter(|identity_no| self.number_to_identity.contains(identity_no))
+				.filter(|identity_no| self.recovery_account_of.get(identity_no) == Some(recovery))
+				.collect()
+		}
+
+		/// Returns the identities within `start..start + limit` that opted
+		/// into the public directory.
+		///
+		/// `limit` is capped to `QUERY_RESULT_LIMIT`. To get the next page the
+		/// query should be repeated with `start + limit` as the new `start`.
+		#[ink(message)]
+		pub fn directory(&self, start: IdentityNo, limit: u32) -> Vec<IdentityNo> {
+			(start..self.latest_identity_no)
+				.take((limit as usize).min(QUERY_RESULT_LIMIT))
+				.filter(|identity_no| self.directory_listed.get(identity_no).unwrap_or(false))
+				.collect()
+		}
+
+		/// Returns the identities that got created within the blocks
+		/// `from..=to`.
+		///
+		/// Only the identity numbers `start..start + limit` are scanned, with
+		/// `limit` capped to `QUERY_RESULT_LIMIT`. To scan the next chunk the
+		/// query should be repeated with `start + limit` as the new `start`.
+		#[ink(message)]
+		pub fn identities_created_between(
+			&self,
+			from: BlockNumber,
+			to: BlockNumber,
+			start: IdentityNo,
+			limit: u32,
+		) -> Vec<IdentityNo> {
+			(start..self.latest_identity_no)
+				.take((limit as usize).min(QUERY_RESULT_LIMIT))
+				.filter(|identity_no| {
+					self.created_at
+						.get(identity_no)
+						.is_some_and(|created_at| (from..=to).contains(&created_at))
+				})
+				.collect()
+		}
+
+		/// Creates an identity and returns the `IdentityNo`.
+		///
+		/// A user can only create one identity.
+		#[ink(message)]
+		pub fn create_identity(&mut self) -> Result<IdentityNo, Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.identity_of.get(caller).is_none(), Error::AlreadyIdentityOwner);
+			ensure!(
+				!self.gated || self.allowlist.get(caller).unwrap_or(false),
+				Error::NotAllowlisted
+			);
+
+			Ok(self.create_identity_for(caller))
+		}
+
+		/// Creates an identity for each of the provided owners and returns
+		/// their `IdentityNo`s in the same order.
+		///
+		/// Owners that already have an identity are skipped and their existing
+		/// `IdentityNo` is returned instead, so a failed batch can safely be
+		/// retried.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn register_batch(&mut self, owners: Vec<AccountId>) -> Result<Vec<IdentityNo>, Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			ensure!(owners.len() <= BATCH_SIZE_LIMIT, Error::BatchSizeExceeded);
+
+			let identity_nos = owners
+				.into_iter()
+				.map(|owner| match self.identity_of.get(owner) {
+					Some(identity_no) => identity_no,
+					None => self.create_identity_for(owner),
+				})
+				.collect();
+
+			Ok(identity_nos)
+		}
+
+		/// Creates identities populated with the given addresses for each of
+		/// the owners and returns their `IdentityNo`s in the same order. Used
+		/// to seed the contract when migrating an existing user base.
+		///
+		/// Owners that already have an identity are skipped and their existing
+		/// `IdentityNo` is returned instead, their addresses are left as is.
+		/// Nothing is created in case any of the addresses is invalid.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn seed_identities(
+			&mut self,
+			entries: Vec<(AccountId, Vec<(ChainId, EncryptedAddress)>)>,
+		) -> Result<Vec<IdentityNo>, Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			ensure!(entries.len() <= BATCH_SIZE_LIMIT, Error::BatchSizeExceeded);
+
+			let mut seeds = Vec::with_capacity(entries.len());
+			for (owner, addresses) in entries.into_iter() {
+				ensure!(
+					addresses.len() <= self.max_addresses_per_identity as usize,
+					Error::TooManyAddresses
+				);
+
+				let mut identity_info = IdentityInfo::default();
+				for (chain, address) in addresses.into_iter() {
+					ensure!(self.chain_info_of.contains(chain.clone()), Error::InvalidChain);
+					identity_info.add_address(chain, address)?;
+				}
+				seeds.push((owner, identity_info));
+			}
+
+			let identity_nos = seeds
+				.into_iter()
+				.map(|(owner, identity_info)| {
+					if let Some(identity_no) = self.identity_of.get(owner) {
+						return identity_no
+					}
+
+					let identity_no = self.create_identity_for(owner);
+					for (chain, address) in identity_info.addresses.iter().cloned() {
+						self.record_address_added(&chain);
+						let seq = self.next_seq(identity_no);
+						self.env().emit_event(AddressAdded { identity_no, chain, address, seq });
+					}
+					self.number_to_identity.insert(identity_no, &identity_info);
+
+					identity_no
+				})
+				.collect();
+
+			Ok(identity_nos)
+		}
+
+		/// Creates an identity without an owner that can be claimed by whoever
+		/// knows the preimage of `claim_code_hash`. Used when migrating
+		/// identities from other systems.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn register_claimable(
+			&mut self,
+			claim_code_hash: [u8; 32],
+		) -> Result<IdentityNo, Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+
+			let identity_no = self.latest_identity_no;
+
+			self.number_to_identity.insert(identity_no, &IdentityInfo::default());
+			self.claim_code_hash_of.insert(identity_no, &claim_code_hash);
+			self.created_at.insert(identity_no, &self.env().block_number());
+
+			self.latest_identity_no = self.latest_identity_no.saturating_add(1);
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(ClaimableIdentityRegistered { identity_no, seq });
+
+			Ok(identity_no)
+		}
+
+		/// Makes the caller the owner of a claimable identity in case the
+		/// Blake2x256 hash of `preimage` matches its claim code hash.
+		///
+		/// While the contract is gated only allowlisted accounts can claim an
+		/// identity.
+		#[ink(message)]
+		pub fn claim_identity(
+			&mut self,
+			identity_no: IdentityNo,
+			preimage: Vec<u8>,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.identity_of.get(caller).is_none(), Error::AlreadyIdentityOwner);
+			ensure!(
+				!self.gated || self.allowlist.get(caller).unwrap_or(false),
+				Error::NotAllowlisted
+			);
+
+			let claim_code_hash =
+				self.claim_code_hash_of.get(identity_no).map_or(Err(Error::InvalidClaim), Ok)?;
+			ensure!(
+				self.env().hash_bytes::<Blake2x256>(&preimage) == claim_code_hash,
+				Error::InvalidClaim
+			);
+
+			self.claim_code_hash_of.remove(identity_no);
+			self.identity_of.insert(caller, &identity_no);
+			self.clear_rotation_from(caller);
+			self.owner_of.insert(identity_no, &caller);
+			self.creator_of.insert(identity_no, &caller);
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(IdentityClaimed { identity_no, owner: caller, seq });
+
+			Ok(())
+		}
+
+		/// Adds an address for a given chain
+		#[ink(message)]
+		pub fn add_address(
+			&mut self,
+			chain: ChainId,
+			address: EncryptedAddress,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			self.ensure_unlocked(identity_no)?;
+
+			ensure!(
+				!self.require_recovery || self.recovery_account_of.contains(identity_no),
+				Error::RecoveryRequired
+			);
+
+			let mut identity_info = self.get_identity_info_of_caller(caller)?;
+
+			ensure!(
+				identity_info.addresses.len() < self.max_addresses_per_identity as usize,
+				Error::TooManyAddresses
+			);
+
+			identity_info.add_address(chain.clone(), address.clone())?;
+			self.number_to_identity.insert(identity_no, &identity_info);
+			self.record_address_added(&chain);
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(AddressAdded { identity_no, chain, address, seq });
+
+			Ok(())
+		}
+
+		/// Adds an address for a given chain.
+		///
+		/// Unlike `add_address` this fails in case the identity already has the
+		/// exact same address on another chain.
+		#[ink(message)]
+		pub fn add_address_unique(
+			&mut self,
+			chain: ChainId,
+			address: EncryptedAddress,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.identity_of.get(caller).is_some(), Error::NotAllowed);
+
+			let identity_info = self.get_identity_info_of_caller(caller)?;
+
+			ensure!(
+				!identity_info
+					.addresses
+					.iter()
+					.any(|(existing_chain, existing)| *existing_chain != chain &&
+						*existing == address),
+				Error::DuplicateAddressAcrossChains
+			);
+
+			self.add_address(chain, address)
+		}
+
+		/// Adds an address for a given chain together with an optional memo.
+		#[ink(message)]
+		pub fn add_address_with_memo(
+			&mut self,
+			chain: ChainId,
+			address: EncryptedAddress,
+			memo: Option<Memo>,
+		) -> Result<(), Error> {
+			ensure!(memo.as_ref().map_or(0, Vec::len) <= MEMO_SIZE_LIMIT, Error::MemoTooLong);
+
+			self.add_address(chain.clone(), address)?;
+			self.set_memo(chain, memo);
+
+			Ok(())
+		}
+
+		/// Updates the address of the given chain
+		#[ink(message)]
+		pub fn update_address(
+			&mut self,
+			chain: ChainId,
+			address: EncryptedAddress,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			self.ensure_unlocked(identity_no)?;
+
+			let mut identity_info = self.get_identity_info_of_caller(caller)?;
+
+			identity_info.update_address(chain.clone(), address.clone())?;
+			self.number_to_identity.insert(identity_no, &identity_info);
+			// The proof was made for the previous address.
+			self.proof_of.remove((identity_no, chain.clone()));
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(AddressUpdated {
+				identity_no,
+				chain,
+				updated_address: address,
+				seq,
+			});
+
+			Ok(())
+		}
+
+		/// Swaps the addresses the caller's identity holds on the two given
+		/// chains. Useful in case the addresses were added under the wrong
+		/// chains.
+		#[ink(message)]
+		pub fn swap_addresses(&mut self, chain_a: ChainId, chain_b: ChainId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			self.ensure_unlocked(identity_no)?;
+
+			// Swapping the address of a chain with itself wouldn't change anything.
+			ensure!(chain_a != chain_b, Error::DuplicateChain);
+
+			let mut identity_info = self.get_identity_info_of_caller(caller)?;
+
+			identity_info.swap_addresses(&chain_a, &chain_b)?;
+			self.number_to_identity.insert(identity_no, &identity_info);
+
+			// The proofs were made for the addresses on the other chain.
+			self.proof_of.remove((identity_no, chain_a.clone()));
+			self.proof_of.remove((identity_no, chain_b.clone()));
+
+			// The memos belong to the addresses, so they move along with them.
+			let memo_a = self.memo_of.get((identity_no, chain_a.clone()));
+			let memo_b = self.memo_of.get((identity_no, chain_b.clone()));
+			self.set_memo(chain_a.clone(), memo_b);
+			self.set_memo(chain_b.clone(), memo_a);
+
+			for chain in [chain_a, chain_b] {
+				let updated_address = identity_info.address_of(&chain).cloned().unwrap_or_default();
+				let seq = self.next_seq(identity_no);
+				self.env()
+					.emit_event(AddressUpdated { identity_no, chain, updated_address, seq });
+			}
+
+			Ok(())
+		}
+
+		/// Stores a proof that the caller controls the address of their
+		/// identity on the specified chain.
+		///
+		/// The proof is stored as is, verifying it is up to off-chain services.
+		#[ink(message)]
+		pub fn set_proof(&mut self, chain: ChainId, proof: AddressProof) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+
+			ensure!(proof.len() <= PROOF_SIZE_LIMIT, Error::ProofSizeExceeded);
+
+			let identity_info = self.get_identity_info_of_caller(caller)?;
+			ensure!(identity_info.address_of(&chain).is_some(), Error::InvalidChain);
+
+			self.proof_of.insert((identity_no, chain.clone()), &proof);
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(ProofSet { identity_no, chain, seq });
+
+			Ok(())
+		}
+
+		/// Returns the proof of address control the identity stored for the
+		/// specified chain.
+		#[ink(message)]
+		pub fn proof_of(&self, identity_no: IdentityNo, chain: ChainId) -> Option<AddressProof> {
+			self.proof_of.get((identity_no, chain))
+		}
+
+		/// Updates the address of the given chain, but only in case the
+		/// currently stored address equals `expected`.
+		///
+		/// Allows clients to detect that another client modified the address
+		/// in the meantime.
+		#[ink(message)]
+		pub fn cas_update_address(
+			&mut self,
+			chain: ChainId,
+			expected: EncryptedAddress,
+			new: EncryptedAddress,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.identity_of.get(caller).is_some(), Error::NotAllowed);
+
+			let identity_info = self.get_identity_info_of_caller(caller)?;
+			let current = identity_info.address_of(&chain).map_or(Err(Error::InvalidChain), Ok)?;
+
+			ensure!(*current == expected, Error::AddressMismatch);
+
+			self.update_address(chain, new)
+		}
+
+		/// Updates the address of the given chain together with its memo.
+		///
+		/// Passing `None` as the memo clears the existing one.
+		#[ink(message)]
+		pub fn update_address_with_memo(
+			&mut self,
+			chain: ChainId,
+			address: EncryptedAddress,
+			memo: Option<Memo>,
+		) -> Result<(), Error> {
+			ensure!(memo.as_ref().map_or(0, Vec::len) <= MEMO_SIZE_LIMIT, Error::MemoTooLong);
+
+			self.update_address(chain.clone(), address)?;
+			self.set_memo(chain, memo);
+
+			Ok(())
+		}
+
+		/// Returns the memo stored alongside the address of the identity on
+		/// the specified chain.
+		#[ink(message)]
+		pub fn memo_of(&self, identity_no: IdentityNo, chain: ChainId) -> Option<Memo> {
+			self.memo_of.get((identity_no, chain))
+		}
+
+		/// Removes the address by chain
+		#[ink(message)]
+		pub fn remove_address(&mut self, chain: ChainId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			self.ensure_unlocked(identity_no)?;
+
+			let mut identity_info = self.get_identity_info_of_caller(caller)?;
+
+			identity_info.remove_address(chain.clone())?;
+			self.number_to_identity.insert(identity_no, &identity_info);
+			self.record_address_removed(identity_no, &chain);
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(AddressRemoved { identity_no, chain, seq });
+
+			Ok(())
+		}
+
+		/// Removes the address by chain in case the identity has one on the
+		/// given chain.
+		///
+		/// Returns whether an address got removed.
+		#[ink(message)]
+		pub fn remove_address_if_present(&mut self, chain: ChainId) -> Result<bool, Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.identity_of.get(caller).is_some(), Error::NotAllowed);
+
+			let identity_info = self.get_identity_info_of_caller(caller)?;
+
+			if identity_info.address_of(&chain).is_none() {
+				return Ok(false)
+			}
+
+			self.remove_address(chain)?;
+
+			Ok(true)
+		}
+
+		/// Removes the addresses of the caller's identity that are on chains
+		/// which are no longer supported.
+		///
+		/// Returns the chains of the removed addresses.
+		#[ink(message)]
+		pub fn prune_stale_addresses(&mut self) -> Result<Vec<ChainId>, Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			self.ensure_unlocked(identity_no)?;
+
+			let mut identity_info = self.get_identity_info_of_caller(caller)?;
+
+			let stale_chains = self.stale_chains_of(identity_no)?;
+			for chain in stale_chains.iter() {
+				identity_info.remove_address(chain.clone())?;
+				self.record_address_removed(identity_no, chain);
+			}
+			self.number_to_identity.insert(identity_no, &identity_info);
+
+			for chain in stale_chains.iter().cloned() {
+				let seq = self.next_seq(identity_no);
+				self.env().emit_event(AddressRemoved { identity_no, chain, seq });
+			}
+
+			Ok(stale_chains)
+		}
+
+		/// Removes an identity
+		///
+		/// Address books that contain the identity are not touched. Their
+		/// owners can clean them up with `remove_identity_everywhere` of the
+		/// address book contract.
+		#[ink(message)]
+		pub fn remove_identity(&mut self) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			self.ensure_unlocked(identity_no)?;
+			let identity_info = self.get_identity_info_of_caller(caller)?;
+
+			// Keep the aggregate counters in sync with the addresses that are
+			// about to be dropped.
+			identity_info
+				.addresses
+				.iter()
+				.for_each(|(chain, _)| self.record_address_removed(identity_no, chain));
+
+			self.identity_of.remove(caller);
+			self.owner_of.remove(identity_no);
+			self.creator_of.remove(identity_no);
+			self.number_to_identity.remove(identity_no);
+			self.discoverable.remove(identity_no);
+			self.created_at.remove(identity_no);
+			self.operational_account_of.remove(identity_no);
+			self.directory_listed.remove(identity_no);
+			self.staged_addresses_of.remove(identity_no);
+			self.locked_until.remove(identity_no);
+			self.successor_of.remove(identity_no);
+			self.clear_rotation(identity_no);
+
+			let seq = self.next_seq(identity_no);
+			self.seq_of.remove(identity_no);
+			self.env().emit_event(IdentityRemoved { identity_no, seq });
+
+			self.notify_subscribers(identity_no);
+
+			Ok(())
+		}
+
+		#[ink(message)]
+		pub fn add_chain(&mut self, chain_id: ChainId, info: ChainInfo) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			// Only an admin can add a chain
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+
+			// A chain can't be added twice.
+			ensure!(!self.chain_info_of.contains(chain_id.clone()), Error::DuplicateChain);
+
+			// The relay chain of a parachain must be supported.
+			if let Some(relay) = info.relay.as_ref() {
+				ensure!(
+					*relay != chain_id && self.chain_info_of.contains(relay),
+					Error::InvalidChain
+				);
+			}
+
+			self.chain_info_of.insert(chain_id.clone(), &info);
+			self.chain_ids.push(chain_id.clone());
+
+			let ChainInfo { account_type, supports_xcm, .. } = info;
+
+			self.env().emit_event(ChainAdded { chain_id, account_type, supports_xcm });
+
+			Ok(())
+		}
+
+		/// Updates the address type and the relay chain of a chain. The relay
+		/// chain gets set in case `new_relay` is `Some` and cleared in case it
+		/// is `Some(None)`.
+		#[ink(message)]
+		pub fn update_chain(
+			&mut self,
+			chain_id: ChainId,
+			new_address_type: Option<AccountType>,
+			new_relay: Option<Option<ChainId>>,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			// Only an admin can update a chain
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+
+			// Ensure that the given chain id exists
+			let mut info =
+				self.chain_info_of.get(chain_id.clone()).map_or(Err(Error::InvalidChain), Ok)?;
+
+			if let Some(account_type) = new_address_type {
+				info.account_type = account_type;
+			}
+
+			if let Some(relay) = new_relay {
+				if let Some(relay) = relay.as_ref() {
+					ensure!(
+						*relay != chain_id && self.chain_info_of.contains(relay),
+						Error::InvalidChain
+					);
+				}
+				info.relay = relay;
+			}
+
+			// Update storage items
+			self.chain_info_of.insert(chain_id.clone(), &info);
+
+			let ChainInfo { account_type, supports_xcm, relay, .. } = info;
+
+			self.env()
+				.emit_event(ChainUpdated { chain_id, account_type, supports_xcm, relay });
+
+			Ok(())
+		}
+
+		/// Removes a chain right away.
+		///
+		/// Only possible while `chain_removal_delay` is zero, otherwise the
+		/// removal has to be scheduled with `schedule_chain_removal`.
+		#[ink(message)]
+		pub fn remove_chain(&mut self, chain_id: ChainId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			// Only an admin can remove a chain
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+
+			// Chains can only be removed right away while there is no
+			// timelock.
+			ensure!(self.chain_removal_delay == 0, Error::ChainRemovalTimelocked);
+
+			// Ensure that the given `chain_id` exists
+			let chain = self.chain_info_of.get(chain_id.clone());
+			ensure!(chain.is_some(), Error::InvalidChain);
+			self.ensure_no_parachains(&chain_id)?;
+
+			self.remove_chain_unchecked(chain_id);
+
+			Ok(())
+		}
+
+		/// Sets the number of blocks that need to pass between scheduling the
+		/// removal of a chain and executing it.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn set_chain_removal_delay(&mut self, delay: BlockNumber) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			self.chain_removal_delay = delay;
+
+			Ok(())
+		}
+
+		/// Sets the maximum number of addresses a single identity can have.
+		/// Identities already above the new maximum keep their addresses but
+		/// can't add new ones.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn set_max_addresses_per_identity(&mut self, max: u32) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			self.max_addresses_per_identity = max;
+
+			Ok(())
+		}
+
+		/// Schedules the removal of a chain. The removal can be executed once
+		/// `chain_removal_delay` blocks have passed.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn schedule_chain_removal(&mut self, chain_id: ChainId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			ensure!(self.chain_info_of.contains(chain_id.clone()), Error::InvalidChain);
+			self.ensure_no_parachains(&chain_id)?;
+
+			let executable_at = self.env().block_number().saturating_add(self.chain_removal_delay);
+			self.pending_chain_removal.insert(chain_id.clone(), &executable_at);
+
+			self.env().emit_event(ChainRemovalScheduled { chain_id, executable_at });
+
+			Ok(())
+		}
+
+		/// Removes a chain whose scheduled removal is due.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn execute_chain_removal(&mut self, chain_id: ChainId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+
+			let executable_at = self
+				.pending_chain_removal
+				.get(chain_id.clone())
+				.map_or(Err(Error::ChainRemovalNotScheduled), Ok)?;
+			ensure!(self.env().block_number() >= executable_at, Error::ChainRemovalTimelocked);
+			ensure!(self.chain_info_of.contains(chain_id.clone()), Error::InvalidChain);
+			// Parachains could have been added since the removal got scheduled.
+			self.ensure_no_parachains(&chain_id)?;
+
+			self.remove_chain_unchecked(chain_id);
+
+			Ok(())
+		}
+
+		/// Cancels the scheduled removal of a chain.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn cancel_chain_removal(&mut self, chain_id: ChainId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			ensure!(
+				self.pending_chain_removal.contains(chain_id.clone()),
+				Error::ChainRemovalNotScheduled
+			);
+
+			self.pending_chain_removal.remove(chain_id.clone());
+			self.env().emit_event(ChainRemovalCancelled { chain_id });
+
+			Ok(())
+		}
+
+		/// Restores the invariant that every chain in `chain_ids` is listed
+		/// exactly once and has a `ChainInfo` stored. Returns the number of
+		/// entries that got removed from `chain_ids`.
+		///
+		/// Since `chain_info_of` can't be enumerated, `ChainInfo`s of chains
+		/// missing from `chain_ids` can't be detected.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn reconcile_chains(&mut self) -> Result<u32, Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+
+			let mut chain_ids: Vec<ChainId> = Vec::with_capacity(self.chain_ids.len());
+			for chain_id in self.chain_ids.iter() {
+				if self.chain_info_of.contains(chain_id) && !chain_ids.contains(chain_id) {
+					chain_ids.push(chain_id.clone());
+				}
+			}
+
+			let corrections = self.chain_ids.len().saturating_sub(chain_ids.len()) as u32;
+			self.chain_ids = chain_ids;
+
+			Ok(corrections)
+		}
+
+		/// Returns whether the given account is an admin.
+		#[ink(message)]
+		pub fn is_admin(&self, account: AccountId) -> bool {
+			self.admins.get(account).unwrap_or(false)
+		}
+
+		/// Adds a new admin.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn add_admin(&mut self, admin: AccountId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			ensure!(!self.is_admin(admin), Error::AlreadyAdmin);
+
+			self.admins.insert(admin, &true);
+			self.admin_count = self.admin_count.saturating_add(1);
+
+			self.env().emit_event(AdminAdded { admin });
+
+			Ok(())
+		}
+
+		/// Removes an admin.
+		///
+		/// Only callable by an admin. The last remaining admin can't be
+		/// removed.
+		#[ink(message)]
+		pub fn remove_admin(&mut self, admin: AccountId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			ensure!(self.is_admin(admin), Error::NotAdmin);
+			ensure!(self.admin_count > 1, Error::CannotRemoveLastAdmin);
+
+			self.admins.remove(admin);
+			self.admin_count = self.admin_count.saturating_sub(1);
+
+			self.env().emit_event(AdminRemoved { admin });
+
+			Ok(())
+		}
+
+		/// Returns the contracts that get notified whenever an identity is
+		/// removed.
+		#[ink(message)]
+		pub fn subscribers(&self) -> Vec<AccountId> {
+			self.subscribers.clone()
+		}
+
+		/// Subscribes a contract to identity removals. Whenever an identity
+		/// is removed the `identity_removed` message of the contract gets
+		/// called with the `IdentityNo` of the removed identity.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn add_subscriber(&mut self, subscriber: AccountId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			ensure!(!self.subscribers.contains(&subscriber), Error::AlreadySubscribed);
+			ensure!(self.subscribers.len() < SUBSCRIBER_LIMIT, Error::TooManySubscribers);
+
+			self.subscribers.push(subscriber);
+
+			Ok(())
+		}
+
+		/// Unsubscribes a contract from identity removals.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn remove_subscriber(&mut self, subscriber: AccountId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			ensure!(self.subscribers.contains(&subscriber), Error::NotSubscribed);
+
+			self.subscribers.retain(|existing| *existing != subscriber);
+
+			Ok(())
+		}
+
+		/// Re-emits the `IdentityCreated` and `AddressAdded` events of the
+		/// existing identities within `start..start + limit`, with `limit`
+		/// capped to `QUERY_RESULT_LIMIT`.
+		///
+		/// This is a maintenance tool that allows indexers which came online
+		/// late or got reset to rebuild their state. Only callable by an admin.
+		///
+		/// The replayed events don't increase the sequence number. All of
+		/// them carry the identity's current `seq`, so they aren't contiguous
+		/// and shouldn't be checked for gaps. Events emitted afterwards
+		/// continue from that `seq`.
+		#[ink(message)]
+		pub fn emit_snapshot(&self, start: IdentityNo, limit: u32) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+
+			(start..self.latest_identity_no)
+				.take((limit as usize).min(QUERY_RESULT_LIMIT))
+				.for_each(|identity_no| {
+					let (Some(owner), Some(identity_info)) =
+						(self.owner_of.get(identity_no), self.number_to_identity.get(identity_no))
+					else {
+						return
+					};
+
+					let seq = self.seq_of(identity_no);
+					self.env().emit_event(IdentityCreated { owner, identity_no, seq });
+					identity_info.addresses.into_iter().for_each(|(chain, address)| {
+						self.env().emit_event(AddressAdded { identity_no, chain, address, seq })
+					});
+				});
+
+			Ok(())
+		}
+
+		/// Sets whether only allowlisted accounts can create an identity.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn set_gated(&mut self, gated: bool) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			self.gated = gated;
+
+			Ok(())
+		}
+
+		/// Sets whether identities need a recovery account before they can add
+		/// addresses.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn set_require_recovery(&mut self, require_recovery: bool) -> Result<(), Error> {
+			let caller = self.env().caller();
 
-			self.number_to_identity.insert(identity_no, &new_identity);
-			self.identity_of.insert(caller, &identity_no);
-			self.owner_of.insert(identity_no, &caller);
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			self.require_recovery = require_recovery;
 
-			self.latest_identity_no = self.latest_identity_no.saturating_add(1);
+			Ok(())
+		}
 
-			self.env().emit_event(IdentityCreated { owner: caller, identity_no });
+		/// Allows `account` to create an identity while the contract is gated.
+		///
+		/// Only callable by an admin.
+		#[ink(message)]
+		pub fn allow(&mut self, account: AccountId) -> Result<(), Error> {
+			let caller = self.env().caller();
 
-			Ok(identity_no)
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			self.allowlist.insert(account, &true);
+
+			Ok(())
 		}
 
-		/// Adds an address for a given chain
+		/// Removes `account` from the allowlist.
+		///
+		/// Only callable by an admin.
 		#[ink(message)]
-		pub fn add_address(
-			&mut self,
-			chain: ChainId,
-			address: EncryptedAddress,
-		) -> Result<(), Error> {
+		pub fn disallow(&mut self, account: AccountId) -> Result<(), Error> {
 			let caller = self.env().caller();
 
-			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			ensure!(self.is_admin(caller), Error::NotAllowed);
+			self.allowlist.remove(account);
 
-			let mut identity_info = self.get_identity_info_of_caller(caller)?;
+			Ok(())
+		}
 
-			identity_info.add_address(chain.clone(), address.clone())?;
-			self.number_to_identity.insert(identity_no, &identity_info);
+		/// Sets the recovery account that will be able to change the ownership
+		/// of the identity.
+		///
+		/// Only callable by the identity owner.
+		#[ink(message)]
+		pub fn set_recovery_account(&mut self, recovery_account: AccountId) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
 
-			self.env().emit_event(AddressAdded { identity_no, chain, address });
+			self.recovery_account_of.insert(identity_no, &recovery_account);
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(RecoveryAccountSet { identity_no, recovery_account, seq });
 
 			Ok(())
 		}
 
-		/// Updates the address of the given chain
+		/// Sets the recovery account of the caller's identity, but only in case
+		/// the current recovery account equals `expected`.
+		///
+		/// Prevents overwriting a recovery account that got changed by another
+		/// client in the meantime.
 		#[ink(message)]
-		pub fn update_address(
+		pub fn cas_set_recovery(
 			&mut self,
-			chain: ChainId,
-			address: EncryptedAddress,
+			expected: Option<AccountId>,
+			new: AccountId,
 		) -> Result<(), Error> {
 			let caller = self.env().caller();
 
 			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
 
-			let mut identity_info = self.get_identity_info_of_caller(caller)?;
+			ensure!(self.recovery_account_of.get(identity_no) == expected, Error::RecoveryMismatch);
 
-			identity_info.update_address(chain.clone(), address.clone())?;
-			self.number_to_identity.insert(identity_no, &identity_info);
+			self.set_recovery_account(new)
+		}
 
-			self.env()
-				.emit_event(AddressUpdated { identity_no, chain, updated_address: address });
+		/// Locks the caller's identity until the given block. While locked the
+		/// identity can't be transferred, its addresses can't be changed and
+		/// it can't be removed.
+		///
+		/// An active lock can only be extended, never shortened.
+		#[ink(message)]
+		pub fn set_locked_until(&mut self, locked_until: BlockNumber) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+
+			if let Some(current) = self.locked_until.get(identity_no) {
+				ensure!(
+					locked_until >= current || current <= self.env().block_number(),
+					Error::IdentityLocked
+				);
+			}
+
+			self.locked_until.insert(identity_no, &locked_until);
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(LockedUntilSet { identity_no, locked_until, seq });
 
 			Ok(())
 		}
 
-		/// Removes the address by chain
+		/// Sets whether the caller's identity is listed in the public
+		/// directory.
 		#[ink(message)]
-		pub fn remove_address(&mut self, chain: ChainId) -> Result<(), Error> {
+		pub fn set_directory_listed(&mut self, listed: bool) -> Result<(), Error> {
 			let caller = self.env().caller();
 
 			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
 
-			let mut identity_info = self.get_identity_info_of_caller(caller)?;
+			self.directory_listed.insert(identity_no, &listed);
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(DirectoryListingSet { identity_no, listed, seq });
 
-			identity_info.remove_address(chain.clone())?;
-			self.number_to_identity.insert(identity_no, &identity_info);
+			Ok(())
+		}
+
+		/// Sets or clears the operational account of the caller's identity.
+		#[ink(message)]
+		pub fn set_operational_account(
+			&mut self,
+			operational_account: Option<AccountId>,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
 
-			self.env().emit_event(AddressRemoved { identity_no, chain });
+			if let Some(account) = operational_account {
+				self.operational_account_of.insert(identity_no, &account);
+			} else {
+				self.operational_account_of.remove(identity_no);
+			}
+			let seq = self.next_seq(identity_no);
+			self.env()
+				.emit_event(OperationalAccountSet { identity_no, operational_account, seq });
 
 			Ok(())
 		}
 
-		/// Removes an identity
+		/// Sets or clears the chain on which the caller prefers to receive
+		/// transfers. The identity needs to have an address on the chain.
+		///
+		/// The preference is cleared automatically once the address on the
+		/// chain gets removed.
 		#[ink(message)]
-		pub fn remove_identity(&mut self) -> Result<(), Error> {
+		pub fn set_receiving_preference(&mut self, chain: Option<ChainId>) -> Result<(), Error> {
 			let caller = self.env().caller();
 
 			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
 
-			self.identity_of.remove(caller);
-			self.owner_of.remove(identity_no);
-			self.number_to_identity.remove(identity_no);
+			if let Some(chain) = chain.clone() {
+				let identity_info = self.get_identity_info_of_caller(caller)?;
+				ensure!(identity_info.address_of(&chain).is_some(), Error::InvalidChain);
 
-			self.env().emit_event(IdentityRemoved { identity_no });
+				self.receiving_preference_of.insert(identity_no, &chain);
+			} else {
+				self.receiving_preference_of.remove(identity_no);
+			}
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(ReceivingPreferenceSet { identity_no, chain, seq });
 
 			Ok(())
 		}
 
+		/// Sets or clears the successor of the caller's identity. Senders can
+		/// choose to be forwarded to the successor in case the identity
+		/// doesn't have an address on a chain.
 		#[ink(message)]
-		pub fn add_chain(&mut self, chain_id: ChainId, info: ChainInfo) -> Result<(), Error> {
+		pub fn set_successor(&mut self, successor: Option<IdentityNo>) -> Result<(), Error> {
 			let caller = self.env().caller();
 
-			// Only the contract owner can add a chain
-			ensure!(caller == self.admin, Error::NotAllowed);
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+
+			if let Some(successor) = successor {
+				ensure!(successor != identity_no, Error::InvalidSuccessor);
+				ensure!(self.number_to_identity.contains(successor), Error::IdentityDoesntExist);
 
-			self.chain_info_of.insert(chain_id.clone(), &info);
-			self.chain_ids.push(chain_id.clone());
+				self.successor_of.insert(identity_no, &successor);
+			} else {
+				self.successor_of.remove(identity_no);
+			}
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(SuccessorSet { identity_no, successor, seq });
+
+			Ok(())
+		}
+
+		/// Sets whether the caller's identity can be added to address books.
+		#[ink(message)]
+		pub fn set_discoverable(&mut self, discoverable: bool) -> Result<(), Error> {
+			let caller = self.env().caller();
 
-			let ChainInfo { account_type } = info;
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
 
-			self.env().emit_event(ChainAdded { chain_id, account_type });
+			self.discoverable.insert(identity_no, &discoverable);
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(DiscoverableSet { identity_no, discoverable, seq });
 
 			Ok(())
 		}
 
+		/// Transfers the ownership of an identity to another account.
+		///
+		/// Only callable by the identity owner or any account that the identity
+		/// owner added as a proxy.
 		#[ink(message)]
-		pub fn update_chain(
+		pub fn transfer_ownership(
 			&mut self,
-			chain_id: ChainId,
-			new_address_type: Option<AccountType>,
+			identity_no: IdentityNo,
+			new_owner: AccountId,
 		) -> Result<(), Error> {
 			let caller = self.env().caller();
 
-			// Only the contract owner can update a chain
-			ensure!(caller == self.admin, Error::NotAllowed);
+			let is_recovery_account = self.recovery_account_of.get(identity_no) == Some(caller);
+			let Some(identity_owner) = self.owner_of(identity_no) else {
+				return Err(Error::NotAllowed)
+			};
 
-			// Ensure that the given chain id exists
-			let mut info =
-				self.chain_info_of.get(chain_id.clone()).map_or(Err(Error::InvalidChain), Ok)?;
+			ensure!(identity_owner == caller || is_recovery_account, Error::NotAllowed);
+			self.ensure_unlocked(identity_no)?;
+			// The new owner cannot already have an identity since we allow only
+			// one identity per account.
+			ensure!(self.identity_of(new_owner).is_none(), Error::AlreadyIdentityOwner);
 
-			if let Some(account_type) = new_address_type {
-				info.account_type = account_type;
+			self.move_ownership(identity_no, identity_owner, new_owner);
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(OwnershipTransferred {
+				identity_no,
+				previous_owner: identity_owner,
+				new_owner,
+				seq,
+			});
+
+			if identity_owner != caller {
+				let seq = self.next_seq(identity_no);
+				self.env().emit_event(RecoveryUsed {
+					identity_no,
+					recovery_account: caller,
+					new_owner,
+					seq,
+				});
 			}
 
-			// Update storage items
-			self.chain_info_of.insert(chain_id.clone(), &info);
+			Ok(())
+		}
 
-			self.env()
-				.emit_event(ChainUpdated { chain_id, account_type: info.account_type });
+		/// Transfers the ownership of multiple identities at once.
+		///
+		/// For every identity the same accounts as for `transfer_ownership` are
+		/// allowed to call this. Nothing is transferred in case any of the
+		/// transfers isn't possible.
+		#[ink(message)]
+		pub fn transfer_many(
+			&mut self,
+			transfers: Vec<(IdentityNo, AccountId)>,
+		) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			ensure!(transfers.len() <= BATCH_SIZE_LIMIT, Error::BatchSizeExceeded);
+
+			let mut previous_owners = Vec::with_capacity(transfers.len());
+			for (index, (identity_no, new_owner)) in transfers.iter().enumerate() {
+				let identity_owner =
+					self.owner_of(*identity_no).map_or(Err(Error::NotAllowed), Ok)?;
+				let is_recovery_account = self.recovery_account_of.get(identity_no) == Some(caller);
+
+				ensure!(identity_owner == caller || is_recovery_account, Error::NotAllowed);
+				// An identity can't be transferred twice within the same batch.
+				ensure!(
+					!transfers[..index].iter().any(|(other, _)| other == identity_no),
+					Error::NotAllowed
+				);
+				self.ensure_unlocked(*identity_no)?;
+				ensure!(
+					self.identity_of(*new_owner).is_none() &&
+						!transfers[..index].iter().any(|(_, other)| other == new_owner),
+					Error::AlreadyIdentityOwner
+				);
+
+				previous_owners.push((identity_owner, identity_owner != caller));
+			}
+
+			for ((identity_no, new_owner), (previous_owner, used_recovery)) in
+				transfers.into_iter().zip(previous_owners.into_iter())
+			{
+				self.move_ownership(identity_no, previous_owner, new_owner);
+
+				let seq = self.next_seq(identity_no);
+				self.env().emit_event(OwnershipTransferred {
+					identity_no,
+					previous_owner,
+					new_owner,
+					seq,
+				});
+
+				if used_recovery {
+					let seq = self.next_seq(identity_no);
+					self.env().emit_event(RecoveryUsed {
+						identity_no,
+						recovery_account: caller,
+						new_owner,
+						seq,
+					});
+				}
+			}
 
 			Ok(())
 		}
 
+		/// Transfers the ownership of the caller's identity to `new_owner`.
+		///
+		/// Unlike `transfer_ownership` the caller can move the identity back
+		/// with `rollback_owner` within `ROTATION_ROLLBACK_WINDOW` blocks, e.g.
+		/// in case the new key turns out to be compromised as well.
 		#[ink(message)]
-		pub fn remove_chain(&mut self, chain_id: ChainId) -> Result<(), Error> {
+		pub fn rotate_owner_key(&mut self, new_owner: AccountId) -> Result<(), Error> {
 			let caller = self.env().caller();
 
-			// Only the contract owner can update a chain
-			ensure!(caller == self.admin, Error::NotAllowed);
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			self.ensure_unlocked(identity_no)?;
+			ensure!(self.identity_of(new_owner).is_none(), Error::AlreadyIdentityOwner);
 
-			// Ensure that the given `chain_id` exists
-			let chain = self.chain_info_of.get(chain_id.clone());
-			ensure!(chain.is_some(), Error::InvalidChain);
+			self.move_ownership(identity_no, caller, new_owner);
 
-			self.chain_info_of.remove(chain_id.clone());
-			self.chain_ids.retain(|c_id| *c_id != chain_id.clone());
+			let rollback_until = self.env().block_number().saturating_add(ROTATION_ROLLBACK_WINDOW);
+			self.rotation_of.insert(
+				caller,
+				&KeyRotation { identity_no, rotated_to: new_owner, rollback_until },
+			);
+			self.rotated_from.insert(identity_no, &caller);
 
-			self.env().emit_event(ChainRemoved { chain_id });
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(OwnershipTransferred {
+				identity_no,
+				previous_owner: caller,
+				new_owner,
+				seq,
+			});
 
 			Ok(())
 		}
 
-		/// Sets the recovery account that will be able to change the ownership
-		/// of the identity.
+		/// Moves the identity the caller rotated away from with
+		/// `rotate_owner_key` back to the caller.
 		///
-		/// Only callable by the identity owner.
+		/// Only possible within `ROTATION_ROLLBACK_WINDOW` blocks after the
+		/// rotation, as long as the identity is still owned by the account it
+		/// got rotated to and the rotation didn't get finalized. Locks set by
+		/// the new owner don't prevent the rollback since the new key might be
+		/// the one at risk.
 		#[ink(message)]
-		pub fn set_recovery_account(&mut self, recovery_account: AccountId) -> Result<(), Error> {
+		pub fn rollback_owner(&mut self) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let KeyRotation { identity_no, rotated_to, rollback_until } =
+				self.rotation_of.get(caller).map_or(Err(Error::NoRotation), Ok)?;
+
+			ensure!(self.env().block_number() <= rollback_until, Error::RotationExpired);
+			ensure!(self.owner_of(identity_no) == Some(rotated_to), Error::NoRotation);
+			ensure!(self.identity_of(caller).is_none(), Error::AlreadyIdentityOwner);
+
+			self.move_ownership(identity_no, rotated_to, caller);
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(OwnershipTransferred {
+				identity_no,
+				previous_owner: rotated_to,
+				new_owner: caller,
+				seq,
+			});
+
+			Ok(())
+		}
+
+		/// Finalizes the key rotation of the caller's identity so that the
+		/// previous owner can no longer roll it back.
+		#[ink(message)]
+		pub fn finalize_rotation(&mut self) -> Result<(), Error> {
 			let caller = self.env().caller();
 
 			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			ensure!(self.rotated_from.contains(identity_no), Error::NoRotation);
 
-			self.recovery_account_of.insert(identity_no, &recovery_account);
-			self.env().emit_event(RecoveryAccountSet { identity_no, recovery_account });
+			self.clear_rotation(identity_no);
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(RotationFinalized { identity_no, seq });
 
 			Ok(())
 		}
 
-		/// Transfers the ownership of an identity to another account.
+		/// Transfers the ownership of an identity to `new_owner` and sets
+		/// `new_recovery` as its recovery account in one go.
 		///
-		/// Only callable by the identity owner or any account that the identity
-		/// owner added as a proxy.
+		/// Only callable by the current recovery account of the identity.
 		#[ink(message)]
-		pub fn transfer_ownership(
+		pub fn complete_recovery(
 			&mut self,
 			identity_no: IdentityNo,
 			new_owner: AccountId,
+			new_recovery: AccountId,
 		) -> Result<(), Error> {
 			let caller = self.env().caller();
 
-			let is_recovery_account = self.recovery_account_of.get(identity_no) == Some(caller);
-			let Some(identity_owner) = self.owner_of(identity_no) else {
-				return Err(Error::NotAllowed)
-			};
-
-			ensure!(identity_owner == caller || is_recovery_account, Error::NotAllowed);
-			// The new owner cannot already have an identity since we allow only
-			// one identity per account.
+			let previous_owner = self.owner_of(identity_no).map_or(Err(Error::NotAllowed), Ok)?;
+			ensure!(self.recovery_account_of.get(identity_no) == Some(caller), Error::NotAllowed);
+			ensure!(new_recovery != new_owner, Error::RecoveryIsOwner);
+			self.ensure_unlocked(identity_no)?;
 			ensure!(self.identity_of(new_owner).is_none(), Error::AlreadyIdentityOwner);
 
-			self.identity_of.remove(identity_owner);
+			self.move_ownership(identity_no, previous_owner, new_owner);
+			self.recovery_account_of.insert(identity_no, &new_recovery);
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(OwnershipTransferred {
+				identity_no,
+				previous_owner,
+				new_owner,
+				seq,
+			});
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(RecoveryUsed {
+				identity_no,
+				recovery_account: caller,
+				new_owner,
+				seq,
+			});
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(RecoveryAccountSet {
+				identity_no,
+				recovery_account: new_recovery,
+				seq,
+			});
+
+			Ok(())
+		}
+
+		/// Transfers the ownership of an identity to another account and
+		/// stages `new_addresses` which the new owner can apply with
+		/// `apply_staged_addresses`.
+		///
+		/// The same accounts as for `transfer_ownership` are allowed to call
+		/// this.
+		#[ink(message)]
+		pub fn transfer_ownership_with_addresses(
+			&mut self,
+			identity_no: IdentityNo,
+			new_owner: AccountId,
+			new_addresses: Vec<(ChainId, EncryptedAddress)>,
+		) -> Result<(), Error> {
+			ensure!(new_addresses.len() <= BATCH_SIZE_LIMIT, Error::BatchSizeExceeded);
+			ensure!(
+				new_addresses.len() <= self.max_addresses_per_identity as usize,
+				Error::TooManyAddresses
+			);
+
+			let mut staged = IdentityInfo::default();
+			for (chain, address) in new_addresses.into_iter() {
+				ensure!(self.chain_info_of.contains(chain.clone()), Error::InvalidChain);
+				staged.add_address(chain, address)?;
+			}
+
+			self.transfer_ownership(identity_no, new_owner)?;
+			self.staged_addresses_of.insert(identity_no, &staged);
+
+			Ok(())
+		}
+
+		/// Replaces all the addresses of the caller's identity with the ones
+		/// staged when the identity got transferred to the caller.
+		#[ink(message)]
+		pub fn apply_staged_addresses(&mut self) -> Result<(), Error> {
+			let caller = self.env().caller();
+
+			let identity_no = self.identity_of.get(caller).map_or(Err(Error::NotAllowed), Ok)?;
+			self.ensure_unlocked(identity_no)?;
+
+			let staged = self
+				.staged_addresses_of
+				.get(identity_no)
+				.map_or(Err(Error::NoStagedAddresses), Ok)?;
+
+			ensure!(
+				!self.require_recovery || self.recovery_account_of.contains(identity_no),
+				Error::RecoveryRequired
+			);
+			// The maximum might have been lowered since the addresses got staged.
+			ensure!(
+				staged.addresses.len() <= self.max_addresses_per_identity as usize,
+				Error::TooManyAddresses
+			);
+			// Chains might have been removed since the addresses got staged.
+			ensure!(
+				staged.addresses.iter().all(|(chain, _)| self.chain_info_of.contains(chain)),
+				Error::InvalidChain
+			);
+
+			let identity_info = self.get_identity_info_of_caller(caller)?;
+
+			for (chain, _) in identity_info.addresses.into_iter() {
+				self.record_address_removed(identity_no, &chain);
+				let seq = self.next_seq(identity_no);
+				self.env().emit_event(AddressRemoved { identity_no, chain, seq });
+			}
+			for (chain, address) in staged.addresses.iter().cloned() {
+				self.record_address_added(&chain);
+				let seq = self.next_seq(identity_no);
+				self.env().emit_event(AddressAdded { identity_no, chain, address, seq });
+			}
+
+			self.number_to_identity.insert(identity_no, &staged);
+			self.staged_addresses_of.remove(identity_no);
+
+			Ok(())
+		}
+
+		fn create_identity_for(&mut self, owner: AccountId) -> IdentityNo {
+			let identity_no = self.latest_identity_no;
+
+			let new_identity: IdentityInfo = Default::default();
+
+			self.number_to_identity.insert(identity_no, &new_identity);
+			self.identity_of.insert(owner, &identity_no);
+			self.clear_rotation_from(owner);
+			self.owner_of.insert(identity_no, &owner);
+			self.creator_of.insert(identity_no, &owner);
+			self.created_at.insert(identity_no, &self.env().block_number());
+
+			self.latest_identity_no = self.latest_identity_no.saturating_add(1);
+
+			let seq = self.next_seq(identity_no);
+			self.env().emit_event(IdentityCreated { owner, identity_no, seq });
+
+			identity_no
+		}
+
+		/// Moves the identity from `previous_owner` to `new_owner`. Any staged
+		/// addresses and pending key rotations are discarded.
+		fn move_ownership(
+			&mut self,
+			identity_no: IdentityNo,
+			previous_owner: AccountId,
+			new_owner: AccountId,
+		) {
+			self.identity_of.remove(previous_owner);
 			self.identity_of.insert(new_owner, &identity_no);
 
 			self.owner_of.insert(identity_no, &new_owner);
+			self.staged_addresses_of.remove(identity_no);
+			self.clear_rotation(identity_no);
+			self.clear_rotation_from(new_owner);
+		}
+
+		/// Discards the pending key rotation of an identity.
+		fn clear_rotation(&mut self, identity_no: IdentityNo) {
+			if let Some(previous_owner) = self.rotated_from.take(identity_no) {
+				self.rotation_of.remove(previous_owner);
+			}
+		}
+
+		/// Discards the key rotation `account` could roll back, since an
+		/// account can't get a second identity.
+		fn clear_rotation_from(&mut self, account: AccountId) {
+			if let Some(rotation) = self.rotation_of.take(account) {
+				self.rotated_from.remove(rotation.identity_no);
+			}
+		}
+
+		/// Notifies all subscribers about the removal of an identity.
+		///
+		/// A failing subscriber doesn't prevent the identity from being
+		/// removed, nor the other subscribers from being notified.
+		fn notify_subscribers(&self, identity_no: IdentityNo) {
+			for subscriber in self.subscribers.iter() {
+				let _ = build_call::<DefaultEnvironment>()
+					.call(*subscriber)
+					.gas_limit(0)
+					.exec_input(
+						ExecutionInput::new(Selector::new(ink::selector_bytes!(
+							"identity_removed"
+						)))
+						.push_arg(identity_no),
+					)
+					.returns::<()>()
+					.try_invoke();
+			}
+		}
+
+		/// Fails in case the identity is locked at the current block.
+		fn ensure_unlocked(&self, identity_no: IdentityNo) -> Result<(), Error> {
+			ensure!(
+				self.env().block_number() >= self.locked_until.get(identity_no).unwrap_or_default(),
+				Error::IdentityLocked
+			);
+
+			Ok(())
+		}
+
+		/// Increments and returns the sequence number of the identity. Needs to
+		/// be called for every event emitted for the identity.
+		fn next_seq(&mut self, identity_no: IdentityNo) -> u32 {
+			let seq = self.seq_of.get(identity_no).unwrap_or_default().saturating_add(1);
+			self.seq_of.insert(identity_no, &seq);
+
+			seq
+		}
+
+		/// Ensures that no chain has `chain_id` as its relay chain, so that a
+		/// relay chain can't be removed while parachains still point to it.
+		fn ensure_no_parachains(&self, chain_id: &ChainId) -> Result<(), Error> {
+			ensure!(
+				!self.chain_ids.iter().any(|id| {
+					self.chain_info_of.get(id).and_then(|info| info.relay).as_ref() ==
+						Some(chain_id)
+				}),
+				Error::ChainHasParachains
+			);
 
 			Ok(())
 		}
 
+		fn remove_chain_unchecked(&mut self, chain_id: ChainId) {
+			self.chain_info_of.remove(chain_id.clone());
+			self.chain_ids.retain(|c_id| *c_id != chain_id.clone());
+			self.pending_chain_removal.remove(chain_id.clone());
+
+			self.env().emit_event(ChainRemoved { chain_id });
+		}
+
+		/// Sets or clears the memo of the caller's address on `chain`.
+		fn set_memo(&mut self, chain: ChainId, memo: Option<Memo>) {
+			let Some(identity_no) = self.identity_of.get(self.env().caller()) else { return };
+
+			if let Some(memo) = memo {
+				self.memo_of.insert((identity_no, chain), &memo);
+			} else {
+				self.memo_of.remove((identity_no, chain));
+			}
+		}
+
+		/// Updates the address counters after an address got added on `chain`.
+		fn record_address_added(&mut self, chain: &ChainId) {
+			let usage = self.chain_usage.get(chain).unwrap_or_default();
+			self.chain_usage.insert(chain, &usage.saturating_add(1));
+			self.total_addresses = self.total_addresses.saturating_add(1);
+		}
+
+		/// Updates the address counters after an address of `identity_no` got
+		/// removed from `chain` and clears the proof and memo that belonged to
+		/// it.
+		fn record_address_removed(&mut self, identity_no: IdentityNo, chain: &ChainId) {
+			self.proof_of.remove((identity_no, chain.clone()));
+			self.memo_of.remove((identity_no, chain.clone()));
+			if self.receiving_preference_of.get(identity_no).as_ref() == Some(chain) {
+				self.receiving_preference_of.remove(identity_no);
+			}
+			let usage = self.chain_usage.get(chain).unwrap_or_default();
+			self.chain_usage.insert(chain, &usage.saturating_sub(1));
+			self.total_addresses = self.total_addresses.saturating_sub(1);
+		}
+
 		pub fn get_identity_info_of_caller(
 			&self,
 			caller: AccountId,
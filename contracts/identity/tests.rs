@@ -3,7 +3,8 @@ use crate::{identity::*, types::*, *};
 use common::types::{AccountType::*, Network::*, *};
 
 use ink::env::{
-	test::{default_accounts, recorded_events, set_caller, DefaultAccounts},
+	hash::{Blake2x256, CryptoHash, HashOutput},
+	test::{advance_block, default_accounts, recorded_events, set_caller, DefaultAccounts},
 	DefaultEnvironment,
 };
 
@@ -18,7 +19,8 @@ fn constructor_works() {
 	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
 
 	assert_eq!(identity.latest_identity_no, 0);
-	assert_eq!(identity.admin, alice);
+	assert!(identity.is_admin(alice));
+	assert_eq!(identity.admin_count, 1);
 	assert_eq!(identity.chain_ids, vec![]);
 	assert_eq!(identity.available_chains(Polkadot), Vec::default());
 	assert_eq!(identity.available_chains(Kusama), Vec::default());
@@ -38,7 +40,7 @@ fn create_identity_works() {
 	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
 		.expect("Failed to decode event");
 
-	let Event::IdentityCreated(IdentityCreated { owner, identity_no }) = decoded_event else {
+	let Event::IdentityCreated(IdentityCreated { owner, identity_no, .. }) = decoded_event else {
 		panic!("IdentityCreated event should be emitted")
 	};
 
@@ -83,10 +85,10 @@ fn add_address_to_identity_works() {
 	let moonbeam: ChainId = (2004, Polkadot);
 
 	assert!(identity
-		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32 })
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
 		.is_ok());
 	assert!(identity
-		.add_chain(moonbeam.clone(), ChainInfo { account_type: AccountId32 })
+		.add_chain(moonbeam.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
 		.is_ok());
 
 	// In reality this address would be encrypted before storing in the contract.
@@ -103,7 +105,8 @@ fn add_address_to_identity_works() {
 	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
 		.expect("Failed to decode event");
 
-	let Event::AddressAdded(AddressAdded { identity_no, chain, address }) = decoded_event else {
+	let Event::AddressAdded(AddressAdded { identity_no, chain, address, .. }) = decoded_event
+	else {
 		panic!("AddressAdded event should be emitted")
 	};
 
@@ -133,10 +136,10 @@ fn update_address_works() {
 
 	assert!(identity.create_identity().is_ok());
 	assert!(identity
-		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32 })
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
 		.is_ok());
 	assert!(identity
-		.add_chain(moonbeam.clone(), ChainInfo { account_type: AccountId32 })
+		.add_chain(moonbeam.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
 		.is_ok());
 
 	assert_eq!(identity.owner_of.get(0), Some(alice));
@@ -168,7 +171,7 @@ fn update_address_works() {
 	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
 		.expect("Failed to decode event");
 
-	let Event::AddressUpdated(AddressUpdated { identity_no, chain, updated_address }) =
+	let Event::AddressUpdated(AddressUpdated { identity_no, chain, updated_address, .. }) =
 		decoded_event
 	else {
 		panic!("AddressUpdated event should be emitted")
@@ -196,7 +199,7 @@ fn remove_address_works() {
 	let polkadot: ChainId = (0, Polkadot);
 	assert!(identity.create_identity().is_ok());
 	assert!(identity
-		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32 })
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
 		.is_ok());
 
 	assert_eq!(identity.owner_of.get(0), Some(alice));
@@ -226,7 +229,7 @@ fn remove_address_works() {
 	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
 		.expect("Failed to decode event");
 
-	let Event::AddressRemoved(AddressRemoved { identity_no, chain }) = decoded_event else {
+	let Event::AddressRemoved(AddressRemoved { identity_no, chain, .. }) = decoded_event else {
 		panic!("AddressRemoved event should be emitted")
 	};
 
@@ -240,6 +243,50 @@ fn remove_address_works() {
 	assert_eq!(identity.remove_address(polkadot), Err(Error::InvalidChain));
 }
 
+#[ink::test]
+fn addresses_are_kept_sorted() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	let moonbeam: ChainId = (2004, Polkadot);
+
+	for chain in [polkadot.clone(), kusama.clone(), moonbeam.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	let address = alice.encode();
+
+	// Alice adds her addresses in one order.
+	assert!(identity.create_identity().is_ok());
+	assert!(identity.add_address(moonbeam.clone(), address.clone()).is_ok());
+	assert!(identity.add_address(polkadot.clone(), address.clone()).is_ok());
+	assert!(identity.add_address(kusama.clone(), address.clone()).is_ok());
+
+	// Bob adds the same addresses in a different order.
+	set_caller::<DefaultEnvironment>(bob);
+	assert!(identity.create_identity().is_ok());
+	assert!(identity.add_address(kusama.clone(), address.clone()).is_ok());
+	assert!(identity.add_address(moonbeam.clone(), address.clone()).is_ok());
+	assert!(identity.add_address(polkadot.clone(), address.clone()).is_ok());
+
+	let alice_identity = identity.number_to_identity.get(0).unwrap();
+	let bob_identity = identity.number_to_identity.get(1).unwrap();
+
+	assert_eq!(
+		alice_identity.addresses,
+		vec![(polkadot, address.clone()), (kusama, address.clone()), (moonbeam.clone(), address)]
+	);
+	assert_eq!(alice_identity.encode(), bob_identity.encode());
+
+	// Lookups still work on the sorted addresses.
+	assert_eq!(identity.transaction_destination(1, moonbeam, false), Ok(alice.encode()));
+}
+
 #[ink::test]
 fn remove_identity_works() {
 	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
@@ -250,7 +297,7 @@ fn remove_identity_works() {
 
 	let polkadot: ChainId = (0, Polkadot);
 	assert!(identity
-		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32 })
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
 		.is_ok());
 
 	assert_eq!(identity.owner_of.get(0), Some(alice));
@@ -280,7 +327,7 @@ fn remove_identity_works() {
 	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
 		.expect("Failed to decode event");
 
-	let Event::IdentityRemoved(IdentityRemoved { identity_no }) = decoded_event else {
+	let Event::IdentityRemoved(IdentityRemoved { identity_no, .. }) = decoded_event else {
 		panic!("IdentityRemoved event should be emitted")
 	};
 
@@ -299,7 +346,7 @@ fn address_size_limit_works() {
 	let polkadot = (0, Polkadot);
 	assert!(identity.create_identity().is_ok());
 	assert!(identity
-		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32 })
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
 		.is_ok());
 
 	let mut polkadot_address: Vec<u8> = vec![];
@@ -313,10 +360,12 @@ fn add_chain_works() {
 	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
 
 	let mut identity = Identity::new();
-	assert_eq!(identity.admin, alice);
+	assert!(identity.is_admin(alice));
 
 	// Adding a chain successful
-	assert!(identity.add_chain((0, Kusama), ChainInfo { account_type: AccountId32 }).is_ok());
+	assert!(identity
+		.add_chain((0, Kusama), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
 
 	// Check emitted events
 	assert_eq!(recorded_events().count(), 1);
@@ -324,14 +373,16 @@ fn add_chain_works() {
 	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
 		.expect("Failed to decode event");
 
-	let Event::ChainAdded(ChainAdded { chain_id, account_type }) = decoded_event else {
+	let Event::ChainAdded(ChainAdded { chain_id, account_type, supports_xcm }) = decoded_event
+	else {
 		panic!("ChainAdded event should be emitted")
 	};
 
 	assert_eq!(chain_id.clone(), (0, Kusama));
 	assert_eq!(account_type, AccountId32);
+	assert!(!supports_xcm);
 
-	let info = ChainInfo { account_type: AccountId32 };
+	let info = ChainInfo { account_type: AccountId32, ..Default::default() };
 
 	// Check storage items updated
 	assert_eq!(identity.chain_info_of.get(chain_id.clone()), Some(info.clone()));
@@ -341,7 +392,10 @@ fn add_chain_works() {
 	// Only the contract creator can add a new chain
 	set_caller::<DefaultEnvironment>(bob);
 	assert_eq!(
-		identity.add_chain((2004, Kusama), ChainInfo { account_type: AccountId32 }),
+		identity.add_chain(
+			(2004, Kusama),
+			ChainInfo { account_type: AccountId32, ..Default::default() }
+		),
 		Err(Error::NotAllowed)
 	);
 
@@ -354,11 +408,13 @@ fn remove_chain_works() {
 	let account_type = AccountId32;
 
 	let mut identity = Identity::new();
-	assert_eq!(identity.admin, alice);
+	assert!(identity.is_admin(alice));
 
 	let chain_id = (0, Kusama);
 	assert!(
-		identity.add_chain(chain_id.clone(), ChainInfo { account_type }).is_ok(),
+		identity
+			.add_chain(chain_id.clone(), ChainInfo { account_type, ..Default::default() })
+			.is_ok(),
 		"Failed to add chain"
 	);
 
@@ -396,32 +452,41 @@ fn update_chain_works() {
 	let account_type = AccountId32;
 
 	let mut identity = Identity::new();
-	assert_eq!(identity.admin, alice);
+	assert!(identity.is_admin(alice));
 
 	let polkadot_id = (0, Polkadot);
 	assert!(
 		identity
-			.add_chain(polkadot_id.clone(), ChainInfo { account_type: account_type.clone() })
+			.add_chain(
+				polkadot_id.clone(),
+				ChainInfo {
+					account_type: account_type.clone(),
+					supports_xcm: true,
+					..Default::default()
+				}
+			)
 			.is_ok(),
 		"Failed to add chain"
 	);
 
-	assert!(identity.add_chain((2000, Polkadot), ChainInfo { account_type }).is_ok());
+	assert!(identity
+		.add_chain((2000, Polkadot), ChainInfo { account_type, ..Default::default() })
+		.is_ok());
 
 	// Only the contract owner can update a chain
 	set_caller::<DefaultEnvironment>(bob);
 	assert_eq!(
-		identity.update_chain(polkadot_id.clone(), Some(AccountKey20)),
+		identity.update_chain(polkadot_id.clone(), Some(AccountKey20), None),
 		Err(Error::NotAllowed)
 	);
 
 	set_caller::<DefaultEnvironment>(alice);
 
 	// Must be an existing chain.
-	assert_eq!(identity.update_chain((3, Polkadot), None), Err(Error::InvalidChain));
+	assert_eq!(identity.update_chain((3, Polkadot), None, None), Err(Error::InvalidChain));
 
 	// Update chain success.
-	assert!(identity.update_chain(polkadot_id.clone(), Some(AccountKey20)).is_ok());
+	assert!(identity.update_chain(polkadot_id.clone(), Some(AccountKey20), None).is_ok());
 
 	// Check the emitted events
 	assert_eq!(recorded_events().count(), 3);
@@ -432,6 +497,8 @@ fn update_chain_works() {
 	let Event::ChainUpdated(ChainUpdated {
 		chain_id: chain_updated,
 		account_type: updated_account_type,
+		supports_xcm,
+		relay,
 	}) = decoded_event
 	else {
 		panic!("ChainUpdated event should be emitted")
@@ -439,6 +506,28 @@ fn update_chain_works() {
 
 	assert_eq!(chain_updated, polkadot_id);
 	assert_eq!(updated_account_type, AccountKey20);
+	assert!(supports_xcm);
+	assert_eq!(relay, None);
+
+	// Error: A chain can't be its own relay chain.
+	assert_eq!(
+		identity.update_chain((2000, Polkadot), None, Some(Some((2000, Polkadot)))),
+		Err(Error::InvalidChain)
+	);
+	// Error: The relay chain has to exist.
+	assert_eq!(
+		identity.update_chain((2000, Polkadot), None, Some(Some((0, Kusama)))),
+		Err(Error::InvalidChain)
+	);
+
+	assert_eq!(
+		identity.update_chain((2000, Polkadot), None, Some(Some(polkadot_id.clone()))),
+		Ok(())
+	);
+	assert_eq!(identity.chains_under_relay(polkadot_id.clone()), vec![(2000, Polkadot)]);
+
+	assert_eq!(identity.update_chain((2000, Polkadot), None, Some(None)), Ok(()));
+	assert_eq!(identity.chains_under_relay(polkadot_id), vec![]);
 }
 
 #[ink::test]
@@ -461,7 +550,7 @@ fn set_recovery_account_works() {
 	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
 		.expect("Failed to decode event");
 
-	let Event::RecoveryAccountSet(RecoveryAccountSet { identity_no, recovery_account }) =
+	let Event::RecoveryAccountSet(RecoveryAccountSet { identity_no, recovery_account, .. }) =
 		decoded_event
 	else {
 		panic!("RecoveryAccountSet event should be emitted")
@@ -470,6 +559,12 @@ fn set_recovery_account_works() {
 	assert_eq!(identity_no, 0);
 	assert_eq!(recovery_account, bob);
 
+	// The recovery account is a topic so that guardians can filter for it.
+	assert_eq!(
+		last_event.topics[2],
+		encoded_topic(b"Identity::RecoveryAccountSet::recovery_account", bob)
+	);
+
 	assert_eq!(identity.recovery_account_of.get(identity_no), Some(bob));
 }
 
@@ -483,7 +578,10 @@ fn transfer_ownership_works() {
 	let polkadot_id = (0, Polkadot);
 	assert!(
 		identity
-			.add_chain(polkadot_id.clone(), ChainInfo { account_type: AccountId32 })
+			.add_chain(
+				polkadot_id.clone(),
+				ChainInfo { account_type: AccountId32, ..Default::default() }
+			)
 			.is_ok(),
 		"Failed to add chain"
 	);
@@ -511,7 +609,21 @@ fn transfer_ownership_works() {
 	assert_eq!(identity.transfer_ownership(identity_no, bob), Err(Error::NotAllowed));
 
 	set_caller::<DefaultEnvironment>(alice);
+	let seq_before = identity.seq_of(identity_no);
 	assert!(identity.transfer_ownership(identity_no, bob).is_ok());
+	assert_eq!(identity.seq_of(identity_no), seq_before + 1);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::OwnershipTransferred(OwnershipTransferred {
+		previous_owner, new_owner, seq, ..
+	}) = decoded_event
+	else {
+		panic!("OwnershipTransferred event should be emitted")
+	};
+	assert_eq!((previous_owner, new_owner, seq), (alice, bob, seq_before + 1));
 
 	// Bob is now the identity owner.
 	assert_eq!(identity.owner_of.get(0), Some(bob));
@@ -530,6 +642,24 @@ fn transfer_ownership_works() {
 	set_caller::<DefaultEnvironment>(alice);
 	assert!(identity.transfer_ownership(identity_no, alice).is_ok());
 
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::RecoveryUsed(RecoveryUsed { identity_no, recovery_account, new_owner, .. }) =
+		decoded_event
+	else {
+		panic!("RecoveryUsed event should be emitted")
+	};
+
+	assert_eq!(identity_no, 0);
+	assert_eq!(recovery_account, alice);
+	assert_eq!(new_owner, alice);
+	assert_eq!(
+		last_event.topics[2],
+		encoded_topic(b"Identity::RecoveryUsed::recovery_account", alice)
+	);
+
 	assert_eq!(identity.owner_of.get(0), Some(alice));
 	assert_eq!(
 		identity.number_to_identity.get(0).unwrap(),
@@ -558,39 +688,39 @@ fn transfer_ownership_fails_when_new_owner_has_an_identity() {
 #[ink::test]
 fn init_with_chains_works() {
 	let chains = vec![
-		ChainInfo { account_type: AccountId32 },
-		ChainInfo { account_type: AccountId32 },
-		ChainInfo { account_type: AccountKey20 },
-		ChainInfo { account_type: AccountId32 },
+		ChainInfo { account_type: AccountId32, ..Default::default() },
+		ChainInfo { account_type: AccountId32, ..Default::default() },
+		ChainInfo { account_type: AccountKey20, ..Default::default() },
+		ChainInfo { account_type: AccountId32, ..Default::default() },
 	];
 	let chain_ids = vec![(0, Polkadot), (2000, Polkadot), (2004, Polkadot), (2006, Polkadot)];
 	let identity = Identity::init_with_chains(chains, chain_ids.clone());
 
 	assert_eq!(
 		identity.chain_info_of((0, Polkadot)),
-		Some(ChainInfo { account_type: AccountId32 })
+		Some(ChainInfo { account_type: AccountId32, ..Default::default() })
 	);
 	assert_eq!(
 		identity.chain_info_of((2000, Polkadot)),
-		Some(ChainInfo { account_type: AccountId32 })
+		Some(ChainInfo { account_type: AccountId32, ..Default::default() })
 	);
 	assert_eq!(
 		identity.chain_info_of((2004, Polkadot)),
-		Some(ChainInfo { account_type: AccountKey20 })
+		Some(ChainInfo { account_type: AccountKey20, ..Default::default() })
 	);
 	assert_eq!(
 		identity.chain_info_of((2006, Polkadot)),
-		Some(ChainInfo { account_type: AccountId32 })
+		Some(ChainInfo { account_type: AccountId32, ..Default::default() })
 	);
 
 	assert_eq!(identity.chain_ids, chain_ids);
 	assert_eq!(
 		identity.available_chains(Polkadot),
 		vec![
-			(0, ChainInfo { account_type: AccountId32 }),
-			(2000, ChainInfo { account_type: AccountId32 }),
-			(2004, ChainInfo { account_type: AccountKey20 }),
-			(2006, ChainInfo { account_type: AccountId32 })
+			(0, ChainInfo { account_type: AccountId32, ..Default::default() }),
+			(2000, ChainInfo { account_type: AccountId32, ..Default::default() }),
+			(2004, ChainInfo { account_type: AccountKey20, ..Default::default() }),
+			(2006, ChainInfo { account_type: AccountId32, ..Default::default() })
 		]
 	);
 }
@@ -598,10 +728,10 @@ fn init_with_chains_works() {
 #[ink::test]
 fn available_chains_works() {
 	let chains = vec![
-		ChainInfo { account_type: AccountId32 },
-		ChainInfo { account_type: AccountId32 },
-		ChainInfo { account_type: AccountKey20 },
-		ChainInfo { account_type: AccountId32 },
+		ChainInfo { account_type: AccountId32, ..Default::default() },
+		ChainInfo { account_type: AccountId32, ..Default::default() },
+		ChainInfo { account_type: AccountKey20, ..Default::default() },
+		ChainInfo { account_type: AccountId32, ..Default::default() },
 	];
 	let chain_ids = vec![(0, Polkadot), (2000, Polkadot), (2004, Polkadot), (2006, Kusama)];
 	let identity = Identity::init_with_chains(chains, chain_ids);
@@ -609,14 +739,14 @@ fn available_chains_works() {
 	assert_eq!(
 		identity.available_chains(Polkadot),
 		vec![
-			(0, ChainInfo { account_type: AccountId32 }),
-			(2000, ChainInfo { account_type: AccountId32 }),
-			(2004, ChainInfo { account_type: AccountKey20 }),
+			(0, ChainInfo { account_type: AccountId32, ..Default::default() }),
+			(2000, ChainInfo { account_type: AccountId32, ..Default::default() }),
+			(2004, ChainInfo { account_type: AccountKey20, ..Default::default() }),
 		]
 	);
 	assert_eq!(
 		identity.available_chains(Kusama),
-		vec![(2006, ChainInfo { account_type: AccountId32 })]
+		vec![(2006, ChainInfo { account_type: AccountId32, ..Default::default() })]
 	);
 }
 
@@ -630,7 +760,10 @@ fn getting_transaction_destination_works() {
 	let polkadot_id = (0, Polkadot);
 	assert!(
 		identity
-			.add_chain(polkadot_id.clone(), ChainInfo { account_type: AccountId32 })
+			.add_chain(
+				polkadot_id.clone(),
+				ChainInfo { account_type: AccountId32, ..Default::default() }
+			)
 			.is_ok(),
 		"Failed to add chain"
 	);
@@ -653,28 +786,2934 @@ fn getting_transaction_destination_works() {
 	);
 
 	assert_eq!(
-		identity.transaction_destination(identity_no, polkadot_id.clone()),
+		identity.transaction_destination(identity_no, polkadot_id.clone(), false),
 		Ok(encoded_address)
 	);
 
 	// Fails since the provided `identity_no` does not exist.
-	assert_eq!(identity.transaction_destination(42, polkadot_id), Err(Error::IdentityDoesntExist));
+	assert_eq!(
+		identity.transaction_destination(42, polkadot_id, false),
+		Err(Error::IdentityDoesntExist)
+	);
 
 	let moonbeam_id = (2004, Polkadot);
 	// Fails because alice does not have an address on the Moonbeam chain.
 	assert!(
 		identity
-			.add_chain(moonbeam_id.clone(), ChainInfo { account_type: AccountId32 })
+			.add_chain(
+				moonbeam_id.clone(),
+				ChainInfo { account_type: AccountId32, ..Default::default() }
+			)
 			.is_ok(),
 		"Failed to add chain"
 	);
 
 	assert_eq!(
-		identity.transaction_destination(identity_no, moonbeam_id),
+		identity.transaction_destination(identity_no, moonbeam_id, false),
 		Err(Error::InvalidChain)
 	);
 }
 
-fn get_default_accounts() -> DefaultAccounts<DefaultEnvironment> {
-	default_accounts::<DefaultEnvironment>()
+#[ink::test]
+fn missing_chains_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	let moonbeam: ChainId = (2004, Polkadot);
+
+	assert!(identity.create_identity().is_ok());
+	for chain in [polkadot.clone(), kusama.clone(), moonbeam.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert!(identity.add_address(polkadot.clone(), alice.encode()).is_ok());
+
+	assert_eq!(
+		identity.missing_chains(0, vec![polkadot.clone(), kusama.clone(), moonbeam.clone()]),
+		Ok(vec![kusama, moonbeam])
+	);
+	assert_eq!(identity.missing_chains(0, vec![polkadot.clone()]), Ok(vec![]));
+
+	// Fails since the provided `identity_no` does not exist.
+	assert_eq!(
+		identity.missing_chains(42, vec![polkadot.clone()]),
+		Err(Error::IdentityDoesntExist)
+	);
+
+	// The number of chains that can be queried at once is limited.
+	assert_eq!(
+		identity.missing_chains(0, vec![polkadot; BATCH_SIZE_LIMIT + 1]),
+		Err(Error::BatchSizeExceeded)
+	);
+}
+
+#[ink::test]
+fn admin_management_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+	assert!(identity.is_admin(alice));
+	assert!(!identity.is_admin(bob));
+
+	// Only admins can add other admins.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.add_admin(bob), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.add_admin(bob), Ok(()));
+	assert_eq!(identity.add_admin(bob), Err(Error::AlreadyAdmin));
+	assert!(identity.is_admin(bob));
+	assert_eq!(identity.admin_count, 2);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::AdminAdded(AdminAdded { admin }) = decoded_event else {
+		panic!("AdminAdded event should be emitted")
+	};
+	assert_eq!(admin, bob);
+
+	// Bob is now able to manage the chains.
+	set_caller::<DefaultEnvironment>(bob);
+	assert!(identity
+		.add_chain((0, Polkadot), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+
+	// Bob removes alice from the admins.
+	assert_eq!(identity.remove_admin(charlie), Err(Error::NotAdmin));
+	assert_eq!(identity.remove_admin(alice), Ok(()));
+	assert!(!identity.is_admin(alice));
+	assert_eq!(identity.admin_count, 1);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::AdminRemoved(AdminRemoved { admin }) = decoded_event else {
+		panic!("AdminRemoved event should be emitted")
+	};
+	assert_eq!(admin, alice);
+
+	// Alice is no longer able to manage the chains.
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.remove_chain((0, Polkadot)), Err(Error::NotAllowed));
+}
+
+#[ink::test]
+fn cannot_remove_last_admin() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.remove_admin(alice), Err(Error::CannotRemoveLastAdmin));
+	assert!(identity.is_admin(alice));
+	assert_eq!(identity.admin_count, 1);
+}
+
+#[ink::test]
+fn register_batch_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, django, .. } =
+		get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	// Bob already has an identity.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	// Only admins can register identities for other accounts.
+	assert_eq!(identity.register_batch(vec![charlie]), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.register_batch(vec![charlie, bob, django]), Ok(vec![1, 0, 2]));
+
+	// Only the new identities are created.
+	assert_eq!(recorded_events().count(), 3);
+	assert_eq!(identity.latest_identity_no, 3);
+	assert_eq!(identity.owner_of.get(1), Some(charlie));
+	assert_eq!(identity.owner_of.get(2), Some(django));
+	assert_eq!(identity.identity_of.get(bob), Some(0));
+
+	// Retrying the same batch doesn't create any new identities.
+	assert_eq!(identity.register_batch(vec![charlie, bob, django]), Ok(vec![1, 0, 2]));
+	assert_eq!(identity.latest_identity_no, 3);
+
+	assert_eq!(
+		identity.register_batch(vec![alice; BATCH_SIZE_LIMIT + 1]),
+		Err(Error::BatchSizeExceeded)
+	);
+}
+
+#[ink::test]
+fn can_edit_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert!(identity.create_identity().is_ok());
+	assert!(identity.set_recovery_account(bob).is_ok());
+
+	assert!(identity.can_edit(0, alice));
+	// The recovery account can only transfer the ownership.
+	assert!(!identity.can_edit(0, bob));
+	assert!(!identity.can_edit(0, charlie));
+	// Nobody can edit an identity that doesn't exist.
+	assert!(!identity.can_edit(1, alice));
+}
+
+#[ink::test]
+fn available_chains_with_usage_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+	assert!(identity
+		.add_chain(kusama.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+
+	assert!(identity.create_identity().is_ok());
+	assert!(identity.add_address(polkadot.clone(), alice.encode()).is_ok());
+	assert!(identity.add_address(kusama.clone(), alice.encode()).is_ok());
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert!(identity.create_identity().is_ok());
+	assert!(identity.add_address(polkadot.clone(), bob.encode()).is_ok());
+
+	assert_eq!(
+		identity.available_chains_with_usage(),
+		vec![
+			(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() }, 2),
+			(kusama.clone(), ChainInfo { account_type: AccountId32, ..Default::default() }, 1)
+		]
+	);
+
+	assert!(identity.remove_address(polkadot.clone()).is_ok());
+
+	assert_eq!(
+		identity.available_chains_with_usage(),
+		vec![
+			(polkadot, ChainInfo { account_type: AccountId32, ..Default::default() }, 1),
+			(kusama, ChainInfo { account_type: AccountId32, ..Default::default() }, 1)
+		]
+	);
+}
+
+#[ink::test]
+fn remove_address_if_present_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+
+	assert!(identity.create_identity().is_ok());
+	assert!(identity.add_address(polkadot.clone(), alice.encode()).is_ok());
+
+	// Bob doesn't have an identity.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.remove_address_if_present(polkadot.clone()), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.remove_address_if_present(polkadot.clone()), Ok(true));
+	assert_eq!(identity.number_to_identity.get(0).unwrap(), IdentityInfo { addresses: vec![] });
+
+	let events_before = recorded_events().count();
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::AddressRemoved(AddressRemoved { identity_no, chain, .. }) = decoded_event else {
+		panic!("AddressRemoved event should be emitted")
+	};
+
+	assert_eq!(identity_no, 0);
+	assert_eq!(chain, polkadot);
+
+	// Removing it a second time is a no-op.
+	assert_eq!(identity.remove_address_if_present(polkadot), Ok(false));
+	assert_eq!(recorded_events().count(), events_before);
+}
+
+#[ink::test]
+fn xcm_chains_works() {
+	let mut identity = Identity::new();
+
+	let xcm_chain =
+		ChainInfo { account_type: AccountId32, supports_xcm: true, ..Default::default() };
+	let non_xcm_chain =
+		ChainInfo { account_type: AccountKey20, supports_xcm: false, ..Default::default() };
+
+	assert!(identity.add_chain((0, Polkadot), xcm_chain.clone()).is_ok());
+	assert!(identity.add_chain((2004, Polkadot), non_xcm_chain.clone()).is_ok());
+	assert!(identity.add_chain((0, Kusama), xcm_chain.clone()).is_ok());
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::ChainAdded(ChainAdded { supports_xcm, .. }) = decoded_event else {
+		panic!("ChainAdded event should be emitted")
+	};
+	assert!(supports_xcm);
+
+	assert_eq!(
+		identity.available_chains(Polkadot),
+		vec![(0, xcm_chain.clone()), (2004, non_xcm_chain)]
+	);
+	assert_eq!(identity.xcm_chains(Polkadot), vec![(0, xcm_chain.clone())]);
+	assert_eq!(identity.xcm_chains(Kusama), vec![(0, xcm_chain)]);
+}
+
+#[ink::test]
+fn chain_info_decodes_legacy_encoding() {
+	// Chains stored before `supports_xcm`, `relay` and `address_format` got
+	// added only have the account type encoded.
+	let legacy_encoding = AccountKey20.encode();
+
+	assert_eq!(
+		<ChainInfo as scale::Decode>::decode(&mut &legacy_encoding[..]),
+		Ok(ChainInfo {
+			account_type: AccountKey20,
+			supports_xcm: false,
+			relay: None,
+			address_format: None
+		})
+	);
+}
+
+#[ink::test]
+fn stale_chains_of_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+	assert!(identity
+		.add_chain(moonbeam.clone(), ChainInfo { account_type: AccountKey20, ..Default::default() })
+		.is_ok());
+
+	assert!(identity.create_identity().is_ok());
+	assert!(identity.add_address(polkadot.clone(), alice.encode()).is_ok());
+	assert!(identity.add_address(moonbeam.clone(), alice.encode()).is_ok());
+
+	assert_eq!(identity.stale_chains_of(0), Ok(vec![]));
+
+	// The admin removes the Moonbeam chain while alice still has an address on it.
+	assert!(identity.remove_chain(moonbeam.clone()).is_ok());
+
+	assert_eq!(identity.stale_chains_of(0), Ok(vec![moonbeam]));
+
+	assert_eq!(identity.stale_chains_of(42), Err(Error::IdentityDoesntExist));
+}
+
+#[ink::test]
+fn prune_stale_addresses_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+	assert!(identity
+		.add_chain(moonbeam.clone(), ChainInfo { account_type: AccountKey20, ..Default::default() })
+		.is_ok());
+
+	assert!(identity.create_identity().is_ok());
+	assert!(identity.add_address(polkadot.clone(), alice.encode()).is_ok());
+	assert!(identity.add_address(moonbeam.clone(), alice.encode()).is_ok());
+
+	assert!(identity.remove_chain(moonbeam.clone()).is_ok());
+
+	// Bob doesn't have an identity.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.prune_stale_addresses(), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.prune_stale_addresses(), Ok(vec![moonbeam.clone()]));
+
+	assert_eq!(
+		identity.number_to_identity.get(0).unwrap(),
+		IdentityInfo { addresses: vec![(polkadot, alice.encode())] }
+	);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::AddressRemoved(AddressRemoved { identity_no, chain, .. }) = decoded_event else {
+		panic!("AddressRemoved event should be emitted")
+	};
+
+	assert_eq!(identity_no, 0);
+	assert_eq!(chain, moonbeam);
+
+	// Nothing left to prune.
+	assert_eq!(identity.prune_stale_addresses(), Ok(vec![]));
+}
+
+#[ink::test]
+fn add_address_unique_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	assert!(identity.create_identity().is_ok());
+
+	assert!(identity.add_address_unique(polkadot.clone(), alice.encode()).is_ok());
+
+	// Error: Adding the same address to the same chain again fails just like
+	// with `add_address`.
+	assert_eq!(
+		identity.add_address_unique(polkadot.clone(), alice.encode()),
+		Err(Error::AddressAlreadyAdded)
+	);
+
+	// The same address can't be added for another chain.
+	assert_eq!(
+		identity.add_address_unique(kusama.clone(), alice.encode()),
+		Err(Error::DuplicateAddressAcrossChains)
+	);
+
+	// A distinct address is fine.
+	assert!(identity.add_address_unique(kusama.clone(), bob.encode()).is_ok());
+	assert_eq!(
+		identity.number_to_identity.get(0).unwrap(),
+		IdentityInfo { addresses: vec![(polkadot, alice.encode()), (kusama, bob.encode())] }
+	);
+}
+
+#[ink::test]
+fn my_destination_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	assert!(identity.create_identity().is_ok());
+	assert!(identity.add_address(polkadot.clone(), alice.encode()).is_ok());
+
+	assert_eq!(identity.my_destination(polkadot.clone()), Ok(alice.encode()));
+	// Alice doesn't have an address on Kusama.
+	assert_eq!(identity.my_destination(kusama), Err(Error::InvalidChain));
+
+	// Bob doesn't have an identity.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.my_destination(polkadot), Err(Error::NotAllowed));
+}
+
+#[ink::test]
+fn chains_under_relay_works() {
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	let parachain = |relay: &ChainId| ChainInfo {
+		account_type: AccountId32,
+		relay: Some(relay.clone()),
+		..Default::default()
+	};
+
+	// The relay chain has to be added before its parachains.
+	assert_eq!(
+		identity.add_chain((2000, Polkadot), parachain(&polkadot)),
+		Err(Error::InvalidChain)
+	);
+
+	assert!(identity.add_chain(polkadot.clone(), ChainInfo::default()).is_ok());
+	assert!(identity.add_chain(kusama.clone(), ChainInfo::default()).is_ok());
+
+	assert!(identity.add_chain((2000, Polkadot), parachain(&polkadot)).is_ok());
+	assert!(identity.add_chain((2004, Polkadot), parachain(&polkadot)).is_ok());
+	assert!(identity.add_chain((2023, Kusama), parachain(&kusama)).is_ok());
+
+	assert_eq!(identity.chain_info_of((2000, Polkadot)), Some(parachain(&polkadot)));
+	assert_eq!(identity.chains_under_relay(polkadot), vec![(2000, Polkadot), (2004, Polkadot)]);
+	assert_eq!(identity.chains_under_relay(kusama), vec![(2023, Kusama)]);
+	assert_eq!(identity.chains_under_relay((2000, Polkadot)), vec![]);
+}
+
+#[ink::test]
+fn swap_addresses_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+	let astar: ChainId = (2006, Polkadot);
+
+	assert!(identity.create_identity().is_ok());
+	for chain in [polkadot.clone(), moonbeam.clone(), astar.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	// Alice mixed up her Polkadot and Moonbeam addresses.
+	assert!(identity.add_address(polkadot.clone(), bob.encode()).is_ok());
+	assert!(identity.add_address(moonbeam.clone(), alice.encode()).is_ok());
+
+	assert_eq!(identity.swap_addresses(polkadot.clone(), astar.clone()), Err(Error::InvalidChain));
+	assert_eq!(identity.swap_addresses(astar.clone(), moonbeam.clone()), Err(Error::InvalidChain));
+
+	// Error: An address can't be swapped with itself.
+	let events_before = recorded_events().count();
+	let seq_before = identity.seq_of(0);
+	assert_eq!(
+		identity.swap_addresses(polkadot.clone(), polkadot.clone()),
+		Err(Error::DuplicateChain)
+	);
+	assert_eq!(recorded_events().count(), events_before);
+	assert_eq!(identity.seq_of(0), seq_before);
+
+	assert_eq!(identity.swap_addresses(polkadot.clone(), moonbeam.clone()), Ok(()));
+	assert_eq!(recorded_events().count(), events_before + 2);
+
+	assert_eq!(
+		identity.number_to_identity.get(0).unwrap(),
+		IdentityInfo { addresses: vec![(polkadot, alice.encode()), (moonbeam, bob.encode())] }
+	);
+
+	// Only identity owners can swap addresses.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.swap_addresses((0, Polkadot), (2004, Polkadot)), Err(Error::NotAllowed));
+}
+
+#[ink::test]
+fn identities_without_recovery_works() {
+	let DefaultAccounts::<DefaultEnvironment> { bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.set_recovery_account(charlie), Ok(()));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+
+	assert_eq!(identity.identities_without_recovery(0, 10), vec![1]);
+	assert_eq!(identity.identities_without_recovery(0, 1), Vec::<IdentityNo>::new());
+
+	// Removed identities are not returned.
+	assert_eq!(identity.remove_identity(), Ok(()));
+	assert_eq!(identity.identities_without_recovery(0, 10), Vec::<IdentityNo>::new());
+}
+
+#[ink::test]
+fn address_format_works() {
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+
+	let polkadot_info = ChainInfo {
+		account_type: AccountId32,
+		address_format: Some(AddressFormat::Ss58 { prefix: 0 }),
+		..Default::default()
+	};
+	let moonbeam_info = ChainInfo {
+		account_type: AccountKey20,
+		relay: Some(polkadot.clone()),
+		..Default::default()
+	};
+
+	assert_eq!(identity.add_chain(polkadot.clone(), polkadot_info.clone()), Ok(()));
+	assert_eq!(identity.add_chain(moonbeam.clone(), moonbeam_info.clone()), Ok(()));
+
+	assert_eq!(
+		identity.chain_info_of(polkadot).map(|info| info.address_format),
+		Some(Some(AddressFormat::Ss58 { prefix: 0 }))
+	);
+	// Chains registered without a format hint default to `None`.
+	assert_eq!(identity.chain_info_of(moonbeam).map(|info| info.address_format), Some(None));
+	assert_eq!(
+		identity.available_chains(Polkadot),
+		vec![(0, polkadot_info), (2004, moonbeam_info)]
+	);
+}
+
+#[ink::test]
+fn set_discoverable_works() {
+	let DefaultAccounts::<DefaultEnvironment> { bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	// Identities are discoverable by default.
+	assert!(identity.is_discoverable(0));
+
+	assert_eq!(identity.set_discoverable(false), Ok(()));
+	assert!(!identity.is_discoverable(0));
+
+	// Test the emitted event
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::DiscoverableSet(DiscoverableSet { identity_no, discoverable, .. }) = decoded_event
+	else {
+		panic!("DiscoverableSet event should be emitted")
+	};
+
+	assert_eq!(identity_no, 0);
+	assert!(!discoverable);
+
+	assert_eq!(identity.set_discoverable(true), Ok(()));
+	assert!(identity.is_discoverable(0));
+
+	// Only identity owners can change the flag.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.set_discoverable(false), Err(Error::NotAllowed));
+}
+
+#[ink::test]
+fn total_addresses_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.total_addresses(), 0);
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(kusama.clone(), alice.encode()), Ok(()));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.add_address(polkadot.clone(), bob.encode()), Ok(()));
+	assert_eq!(identity.total_addresses(), 3);
+
+	// Updating an address doesn't change the total.
+	assert_eq!(identity.update_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.total_addresses(), 3);
+
+	// Failed removals don't change the total either.
+	assert_eq!(identity.remove_address(kusama.clone()), Err(Error::InvalidChain));
+	assert_eq!(identity.remove_address(polkadot.clone()), Ok(()));
+	assert_eq!(identity.total_addresses(), 2);
+
+	// Removing an identity drops all of its addresses.
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.remove_identity(), Ok(()));
+	assert_eq!(identity.total_addresses(), 0);
+}
+
+#[ink::test]
+fn remove_identity_updates_counters() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(kusama.clone(), alice.encode()), Ok(()));
+
+	assert_eq!(identity.total_addresses(), 2);
+	assert_eq!(identity.chain_usage.get(&polkadot), Some(1));
+	assert_eq!(identity.chain_usage.get(&kusama), Some(1));
+
+	assert_eq!(identity.remove_identity(), Ok(()));
+
+	assert_eq!(identity.total_addresses(), 0);
+	assert_eq!(identity.chain_usage.get(&polkadot), Some(0));
+	assert_eq!(identity.chain_usage.get(&kusama), Some(0));
+}
+
+#[ink::test]
+fn set_proof_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	// Error: Only identity owners can set proofs.
+	assert_eq!(identity.set_proof(polkadot.clone(), vec![1; 64]), Err(Error::NotAllowed));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+
+	// Error: There is no address on Kusama to prove control of.
+	assert_eq!(identity.set_proof(kusama.clone(), vec![1; 64]), Err(Error::InvalidChain));
+	// Error: The proof is too large.
+	assert_eq!(
+		identity.set_proof(polkadot.clone(), vec![1; PROOF_SIZE_LIMIT + 1]),
+		Err(Error::ProofSizeExceeded)
+	);
+
+	assert_eq!(identity.proof_of(0, polkadot.clone()), None);
+	assert_eq!(identity.set_proof(polkadot.clone(), vec![1; 64]), Ok(()));
+	assert_eq!(identity.proof_of(0, polkadot.clone()), Some(vec![1; 64]));
+
+	// Test the emitted event
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::ProofSet(ProofSet { identity_no, chain, .. }) = decoded_event else {
+		panic!("ProofSet event should be emitted")
+	};
+
+	assert_eq!(identity_no, 0);
+	assert_eq!(chain, polkadot);
+
+	// The proof gets cleared together with the address.
+	assert_eq!(identity.remove_address(polkadot.clone()), Ok(()));
+	assert_eq!(identity.proof_of(0, polkadot), None);
+}
+
+#[ink::test]
+fn gated_creation_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	// Error: Only admins can gate the contract or manage the allowlist.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.set_gated(true), Err(Error::NotAllowed));
+	assert_eq!(identity.allow(bob), Err(Error::NotAllowed));
+	assert_eq!(identity.disallow(bob), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.set_gated(true), Ok(()));
+	assert_eq!(identity.allow(bob), Ok(()));
+
+	// Error: Charlie isn't allowlisted.
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.create_identity(), Err(Error::NotAllowlisted));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	// Once the gate is lifted anyone can create an identity.
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.disallow(bob), Ok(()));
+	assert_eq!(identity.set_gated(false), Ok(()));
+
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.create_identity(), Ok(1));
+}
+
+#[ink::test]
+fn identities_created_between_works() {
+	let DefaultAccounts::<DefaultEnvironment> { bob, charlie, django, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	// Each identity gets created in a new block.
+	for account in [bob, charlie, django] {
+		advance_block::<DefaultEnvironment>();
+		set_caller::<DefaultEnvironment>(account);
+		assert!(identity.create_identity().is_ok());
+	}
+
+	assert_eq!(identity.created_at.get(0), Some(1));
+	assert_eq!(identity.created_at.get(2), Some(3));
+
+	assert_eq!(identity.identities_created_between(2, 3, 0, 10), vec![1, 2]);
+	assert_eq!(identity.identities_created_between(0, 1, 0, 10), vec![0]);
+
+	// Only the identities within the scanned range are returned.
+	assert_eq!(identity.identities_created_between(0, 10, 0, 2), vec![0, 1]);
+	assert_eq!(identity.identities_created_between(0, 10, 2, 2), vec![2]);
+
+	// Removed identities are not part of any cohort.
+	assert_eq!(identity.remove_identity(), Ok(()));
+	assert_eq!(identity.identities_created_between(0, 10, 0, 10), vec![0, 1]);
+}
+
+#[ink::test]
+fn destinations_on_chain_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.add_address(polkadot.clone(), bob.encode()), Ok(()));
+
+	// Charlie doesn't have an address on Polkadot.
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.create_identity(), Ok(2));
+
+	assert_eq!(
+		identity.destinations_on_chain(vec![0, 1, 2, 3], polkadot.clone()),
+		Ok(vec![
+			(0, Ok(alice.encode())),
+			(1, Ok(bob.encode())),
+			(2, Err(Error::InvalidChain)),
+			(3, Err(Error::IdentityDoesntExist)),
+		])
+	);
+	assert_eq!(
+		identity
+			.destinations_on_chain(vec![0; BATCH_SIZE_LIMIT], polkadot.clone())
+			.map(|d| d.len()),
+		Ok(BATCH_SIZE_LIMIT)
+	);
+
+	// Error: Too many receivers.
+	assert_eq!(
+		identity.destinations_on_chain(vec![0; BATCH_SIZE_LIMIT + 1], polkadot),
+		Err(Error::BatchSizeExceeded)
+	);
+}
+
+#[ink::test]
+fn set_operational_account_works() {
+	let DefaultAccounts::<DefaultEnvironment> { bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	// Error: Only identity owners can set an operational account.
+	assert_eq!(identity.set_operational_account(Some(bob)), Err(Error::NotAllowed));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.operational_account_of(0), None);
+
+	assert_eq!(identity.set_operational_account(Some(bob)), Ok(()));
+	assert_eq!(identity.operational_account_of(0), Some(bob));
+
+	// Test the emitted event
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::OperationalAccountSet(OperationalAccountSet {
+		identity_no,
+		operational_account,
+		..
+	}) = decoded_event
+	else {
+		panic!("OperationalAccountSet event should be emitted")
+	};
+
+	assert_eq!(identity_no, 0);
+	assert_eq!(operational_account, Some(bob));
+
+	assert_eq!(identity.set_operational_account(None), Ok(()));
+	assert_eq!(identity.operational_account_of(0), None);
+
+	// The operational account gets cleared together with the identity.
+	assert_eq!(identity.set_operational_account(Some(charlie)), Ok(()));
+	assert_eq!(identity.remove_identity(), Ok(()));
+	assert_eq!(identity.operational_account_of(0), None);
+}
+
+#[ink::test]
+fn address_memo_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	// Error: The memo is too long.
+	assert_eq!(
+		identity.add_address_with_memo(
+			polkadot.clone(),
+			alice.encode(),
+			Some(vec![1; MEMO_SIZE_LIMIT + 1])
+		),
+		Err(Error::MemoTooLong)
+	);
+	assert_eq!(identity.number_to_identity.get(0).unwrap(), IdentityInfo::default());
+
+	assert_eq!(
+		identity.add_address_with_memo(polkadot.clone(), alice.encode(), Some(vec![42])),
+		Ok(())
+	);
+	assert_eq!(identity.memo_of(0, polkadot.clone()), Some(vec![42]));
+
+	// Addresses added without a memo don't have one.
+	assert_eq!(identity.add_address(kusama.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.memo_of(0, kusama.clone()), None);
+
+	assert_eq!(
+		identity.update_address_with_memo(kusama.clone(), bob.encode(), Some(vec![7])),
+		Ok(())
+	);
+	assert_eq!(identity.memo_of(0, kusama.clone()), Some(vec![7]));
+
+	assert_eq!(identity.update_address_with_memo(kusama.clone(), bob.encode(), None), Ok(()));
+	assert_eq!(identity.memo_of(0, kusama), None);
+
+	// The memo gets cleared together with the address.
+	assert_eq!(identity.remove_address(polkadot.clone()), Ok(()));
+	assert_eq!(identity.memo_of(0, polkadot), None);
+}
+
+#[ink::test]
+fn exists_works() {
+	let mut identity = Identity::new();
+
+	assert!(!identity.exists(0));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert!(identity.exists(0));
+	assert!(!identity.exists(1));
+
+	assert_eq!(identity.remove_identity(), Ok(()));
+	assert!(!identity.exists(0));
+}
+
+#[ink::test]
+fn addresses_on_network_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), moonbeam.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.addresses_on_network(0, Polkadot), Err(Error::IdentityDoesntExist));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.addresses_on_network(0, Kusama), Ok(vec![]));
+
+	for chain in [polkadot.clone(), moonbeam.clone(), kusama.clone()] {
+		assert_eq!(identity.add_address(chain, alice.encode()), Ok(()));
+	}
+
+	assert_eq!(
+		identity.addresses_on_network(0, Polkadot),
+		Ok(vec![(polkadot, alice.encode()), (moonbeam, alice.encode())])
+	);
+	assert_eq!(identity.addresses_on_network(0, Kusama), Ok(vec![(kusama, alice.encode())]));
+}
+
+#[ink::test]
+fn require_recovery_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+
+	assert_eq!(identity.set_require_recovery(true), Ok(()));
+
+	// Error: Only admins can change the policy.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.set_require_recovery(false), Err(Error::NotAllowed));
+
+	// Error: Bob doesn't have a recovery account yet.
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), bob.encode()), Err(Error::RecoveryRequired));
+
+	assert_eq!(identity.set_recovery_account(charlie), Ok(()));
+	assert_eq!(identity.add_address(polkadot.clone(), bob.encode()), Ok(()));
+
+	// Without the policy no recovery account is needed.
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.set_require_recovery(false), Ok(()));
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.add_address(polkadot, alice.encode()), Ok(()));
+}
+
+#[ink::test]
+fn sendable_chains_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), moonbeam.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(moonbeam.clone(), alice.encode()), Ok(()));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.add_address(moonbeam.clone(), bob.encode()), Ok(()));
+	assert_eq!(identity.add_address(kusama.clone(), bob.encode()), Ok(()));
+
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.create_identity(), Ok(2));
+	assert_eq!(identity.add_address(kusama, charlie.encode()), Ok(()));
+
+	assert_eq!(identity.sendable_chains(0, 1), Ok(vec![moonbeam.clone()]));
+	assert_eq!(identity.sendable_chains(1, 0), Ok(vec![moonbeam]));
+
+	// Alice and Charlie don't share any chain.
+	assert_eq!(identity.sendable_chains(0, 2), Ok(vec![]));
+
+	assert_eq!(identity.sendable_chains(0, 3), Err(Error::IdentityDoesntExist));
+	assert_eq!(identity.sendable_chains(3, 0), Err(Error::IdentityDoesntExist));
+}
+
+#[ink::test]
+fn emit_snapshot_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(kusama.clone(), alice.encode()), Ok(()));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.add_address(polkadot, bob.encode()), Ok(()));
+
+	// Charlie's identity gets removed so it is not part of the snapshot.
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.create_identity(), Ok(2));
+	assert_eq!(identity.remove_identity(), Ok(()));
+
+	// Error: Only admins can emit a snapshot.
+	assert_eq!(identity.emit_snapshot(0, 10), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+
+	let events_before = recorded_events().count();
+	assert_eq!(identity.emit_snapshot(0, 10), Ok(()));
+	// Two `IdentityCreated` and three `AddressAdded` events.
+	assert_eq!(recorded_events().count(), events_before + 5);
+
+	let events_before = recorded_events().count();
+	assert_eq!(identity.emit_snapshot(1, 1), Ok(()));
+	assert_eq!(recorded_events().count(), events_before + 2);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::AddressAdded(AddressAdded { identity_no, chain, address, seq }) = decoded_event
+	else {
+		panic!("AddressAdded event should be emitted")
+	};
+
+	assert_eq!(identity_no, 1);
+	assert_eq!(chain, (0, Polkadot));
+	assert_eq!(address, bob.encode());
+
+	// The replayed events carry the current sequence number without
+	// increasing it.
+	assert_eq!(seq, identity.seq_of(1));
+	assert_eq!(identity.seq_of(1), 2);
+}
+
+#[ink::test]
+fn cas_update_address_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+
+	assert_eq!(identity.cas_update_address(polkadot.clone(), alice.encode(), bob.encode()), Ok(()));
+	assert_eq!(identity.transaction_destination(0, polkadot.clone(), false), Ok(bob.encode()));
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::AddressUpdated(AddressUpdated { identity_no, chain, updated_address, .. }) =
+		decoded_event
+	else {
+		panic!("AddressUpdated event should be emitted")
+	};
+
+	assert_eq!(identity_no, 0);
+	assert_eq!(chain, polkadot);
+	assert_eq!(updated_address, bob.encode());
+
+	// Error: The expected address is stale.
+	assert_eq!(
+		identity.cas_update_address(polkadot.clone(), alice.encode(), charlie.encode()),
+		Err(Error::AddressMismatch)
+	);
+	assert_eq!(identity.transaction_destination(0, polkadot, false), Ok(bob.encode()));
+
+	// Error: There is no address on Kusama.
+	assert_eq!(
+		identity.cas_update_address(kusama, alice.encode(), charlie.encode()),
+		Err(Error::InvalidChain)
+	);
+}
+
+#[ink::test]
+fn directory_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	// Error: Only identity owners can opt into the directory.
+	assert_eq!(identity.set_directory_listed(true), Err(Error::NotAllowed));
+
+	for account in [alice, bob, charlie] {
+		set_caller::<DefaultEnvironment>(account);
+		assert!(identity.create_identity().is_ok());
+	}
+
+	// Identities are not listed by default.
+	assert_eq!(identity.directory(0, 10), Vec::<IdentityNo>::new());
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.set_directory_listed(true), Ok(()));
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.set_directory_listed(true), Ok(()));
+
+	assert_eq!(identity.directory(0, 10), vec![0, 2]);
+
+	// Unrelated changes to the identity don't affect the listing.
+	assert_eq!(identity.set_operational_account(None), Ok(()));
+	assert_eq!(identity.directory(0, 10), vec![0, 2]);
+	assert_eq!(identity.directory(0, 2), vec![0]);
+	assert_eq!(identity.directory(2, 2), vec![2]);
+
+	// Charlie opts out again.
+	assert_eq!(identity.set_directory_listed(false), Ok(()));
+	assert_eq!(identity.directory(0, 10), vec![0]);
+
+	// Removed identities are no longer listed.
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.remove_identity(), Ok(()));
+	assert_eq!(identity.directory(0, 10), Vec::<IdentityNo>::new());
+}
+
+#[ink::test]
+fn current_owner_is_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert!(!identity.current_owner_is(0, alice));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert!(identity.current_owner_is(0, alice));
+	assert!(!identity.current_owner_is(0, bob));
+
+	assert_eq!(identity.transfer_ownership(0, bob), Ok(()));
+	assert!(!identity.current_owner_is(0, alice));
+	assert!(identity.current_owner_is(0, bob));
+}
+
+#[ink::test]
+fn chain_removal_timelock_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+
+	assert_eq!(identity.set_chain_removal_delay(10), Ok(()));
+
+	// Error: Only admins can manage chain removals.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.set_chain_removal_delay(0), Err(Error::NotAllowed));
+	assert_eq!(identity.schedule_chain_removal(polkadot.clone()), Err(Error::NotAllowed));
+	assert_eq!(identity.execute_chain_removal(polkadot.clone()), Err(Error::NotAllowed));
+	assert_eq!(identity.cancel_chain_removal(polkadot.clone()), Err(Error::NotAllowed));
+	set_caller::<DefaultEnvironment>(alice);
+
+	// Error: The chain can't be removed right away due to the timelock.
+	assert_eq!(identity.remove_chain(polkadot.clone()), Err(Error::ChainRemovalTimelocked));
+	assert_eq!(
+		identity.execute_chain_removal(polkadot.clone()),
+		Err(Error::ChainRemovalNotScheduled)
+	);
+	assert_eq!(identity.schedule_chain_removal((1, Polkadot)), Err(Error::InvalidChain));
+
+	assert_eq!(identity.schedule_chain_removal(polkadot.clone()), Ok(()));
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::ChainRemovalScheduled(ChainRemovalScheduled { chain_id, executable_at }) =
+		decoded_event
+	else {
+		panic!("ChainRemovalScheduled event should be emitted")
+	};
+
+	assert_eq!(chain_id, polkadot);
+	assert_eq!(executable_at, 10);
+
+	// Error: The delay hasn't passed yet.
+	for _ in 0..9 {
+		advance_block::<DefaultEnvironment>();
+	}
+	assert_eq!(
+		identity.execute_chain_removal(polkadot.clone()),
+		Err(Error::ChainRemovalTimelocked)
+	);
+
+	// The removal can be cancelled.
+	assert_eq!(identity.cancel_chain_removal(polkadot.clone()), Ok(()));
+	assert_eq!(
+		identity.execute_chain_removal(polkadot.clone()),
+		Err(Error::ChainRemovalNotScheduled)
+	);
+	assert_eq!(
+		identity.cancel_chain_removal(polkadot.clone()),
+		Err(Error::ChainRemovalNotScheduled)
+	);
+
+	assert_eq!(identity.schedule_chain_removal(polkadot.clone()), Ok(()));
+	for _ in 0..10 {
+		advance_block::<DefaultEnvironment>();
+	}
+	assert_eq!(identity.execute_chain_removal(polkadot.clone()), Ok(()));
+	assert_eq!(identity.chain_info_of(polkadot.clone()), None);
+	assert_eq!(identity.chain_ids, vec![]);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::ChainRemoved(ChainRemoved { chain_id }) = decoded_event else {
+		panic!("ChainRemoved event should be emitted")
+	};
+
+	assert_eq!(chain_id, polkadot);
+}
+
+#[ink::test]
+fn claim_identity_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let claim_code = b"secret claim code".to_vec();
+	let mut claim_code_hash = <Blake2x256 as HashOutput>::Type::default();
+	Blake2x256::hash(&claim_code, &mut claim_code_hash);
+
+	// Error: Only admins can register claimable identities.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.register_claimable(claim_code_hash), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.register_claimable(claim_code_hash), Ok(0));
+	assert!(identity.exists(0));
+	assert_eq!(identity.owner_of(0), None);
+
+	// Error: Wrong claim code.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.claim_identity(0, b"wrong code".to_vec()), Err(Error::InvalidClaim));
+	// Error: Identity 1 isn't claimable.
+	assert_eq!(identity.claim_identity(1, claim_code.clone()), Err(Error::InvalidClaim));
+
+	assert_eq!(identity.claim_identity(0, claim_code.clone()), Ok(()));
+	assert_eq!(identity.owner_of(0), Some(bob));
+	assert_eq!(identity.identity_of(bob), Some(0));
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::IdentityClaimed(IdentityClaimed { identity_no, owner, .. }) = decoded_event else {
+		panic!("IdentityClaimed event should be emitted")
+	};
+
+	assert_eq!(identity_no, 0);
+	assert_eq!(owner, bob);
+
+	// Error: An identity can only be claimed once.
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.claim_identity(0, claim_code), Err(Error::InvalidClaim));
+}
+
+#[ink::test]
+fn limits_works() {
+	let identity = Identity::new();
+
+	assert_eq!(
+		identity.limits(),
+		Limits {
+			address_size_limit: ADDRESS_SIZE_LIMIT as u32,
+			proof_size_limit: PROOF_SIZE_LIMIT as u32,
+			memo_size_limit: MEMO_SIZE_LIMIT as u32,
+			batch_size_limit: BATCH_SIZE_LIMIT as u32,
+			query_result_limit: QUERY_RESULT_LIMIT as u32,
+			max_addresses_per_identity: DEFAULT_MAX_ADDRESSES_PER_IDENTITY,
+		}
+	);
+}
+
+#[ink::test]
+fn apply_staged_addresses_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+
+	// Error: The staged addresses have to be on supported chains.
+	assert_eq!(
+		identity.transfer_ownership_with_addresses(0, bob, vec![((1, Kusama), bob.encode())]),
+		Err(Error::InvalidChain)
+	);
+	// Error: Only one address per chain can be staged.
+	assert_eq!(
+		identity.transfer_ownership_with_addresses(
+			0,
+			bob,
+			vec![(kusama.clone(), bob.encode()), (kusama.clone(), bob.encode())]
+		),
+		Err(Error::AddressAlreadyAdded)
+	);
+	assert_eq!(identity.owner_of(0), Some(alice));
+
+	assert_eq!(
+		identity.transfer_ownership_with_addresses(0, bob, vec![(kusama.clone(), bob.encode())]),
+		Ok(())
+	);
+	assert_eq!(identity.owner_of(0), Some(bob));
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+	assert!(matches!(
+		decoded_event,
+		Event::OwnershipTransferred(OwnershipTransferred { identity_no: 0, .. })
+	));
+
+	// The addresses are only staged until the new owner applies them.
+	assert_eq!(
+		identity.number_to_identity.get(0).unwrap(),
+		IdentityInfo { addresses: vec![(polkadot.clone(), alice.encode())] }
+	);
+
+	// Error: Only the new owner can apply the staged addresses.
+	assert_eq!(identity.apply_staged_addresses(), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(bob);
+	// Unrelated changes to the identity don't discard the staged addresses.
+	assert_eq!(identity.set_operational_account(None), Ok(()));
+	assert_eq!(identity.apply_staged_addresses(), Ok(()));
+	assert_eq!(
+		identity.number_to_identity.get(0).unwrap(),
+		IdentityInfo { addresses: vec![(kusama.clone(), bob.encode())] }
+	);
+	assert_eq!(identity.total_addresses(), 1);
+	assert_eq!(identity.chain_usage.get(&polkadot), Some(0));
+	assert_eq!(identity.chain_usage.get(&kusama), Some(1));
+
+	// Error: The staged addresses can only be applied once.
+	assert_eq!(identity.apply_staged_addresses(), Err(Error::NoStagedAddresses));
+}
+
+#[ink::test]
+fn is_evm_chain_works() {
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+	assert!(identity
+		.add_chain(moonbeam.clone(), ChainInfo { account_type: AccountKey20, ..Default::default() })
+		.is_ok());
+
+	assert_eq!(identity.is_evm_chain(polkadot), Some(false));
+	assert_eq!(identity.is_evm_chain(moonbeam), Some(true));
+	assert_eq!(identity.is_evm_chain((0, Kusama)), None);
+}
+
+#[ink::test]
+fn owners_in_range_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, django, .. } =
+		get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	for account in [alice, bob, charlie, django] {
+		set_caller::<DefaultEnvironment>(account);
+		assert!(identity.create_identity().is_ok());
+	}
+
+	// Charlie removes his identity.
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.remove_identity(), Ok(()));
+
+	let mut expected = vec![alice, bob, django];
+	expected.sort();
+	assert_eq!(identity.owners_in_range(0, 10), expected);
+
+	assert_eq!(identity.owners_in_range(1, 3), vec![bob]);
+	assert!(identity.owners_in_range(4, 10).is_empty());
+}
+
+#[ink::test]
+fn storage_estimate_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+
+	assert_eq!(identity.storage_estimate(0), Err(Error::IdentityDoesntExist));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	let empty_estimate = identity.storage_estimate(0).unwrap();
+	assert_eq!(empty_estimate, IdentityInfo::default().encode().len() as u32);
+
+	assert_eq!(identity.add_address(polkadot, alice.encode()), Ok(()));
+	assert!(identity.storage_estimate(0).unwrap() > empty_estimate);
+	assert_eq!(
+		identity.storage_estimate(0),
+		Ok(identity.number_to_identity.get(0).unwrap().encode().len() as u32)
+	);
+}
+
+#[ink::test]
+fn seed_identities_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	// Bob already has an identity.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	// Error: Only admins can seed identities.
+	assert_eq!(identity.seed_identities(vec![]), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+
+	// Error: Chain 1 on Kusama isn't supported, so nothing gets created.
+	assert_eq!(
+		identity.seed_identities(vec![
+			(alice, vec![(polkadot.clone(), alice.encode())]),
+			(charlie, vec![((1, Kusama), charlie.encode())]),
+		]),
+		Err(Error::InvalidChain)
+	);
+	assert_eq!(identity.identity_of(alice), None);
+
+	// Error: Too many entries.
+	assert_eq!(
+		identity.seed_identities(vec![(alice, vec![]); BATCH_SIZE_LIMIT + 1]),
+		Err(Error::BatchSizeExceeded)
+	);
+
+	assert_eq!(
+		identity.seed_identities(vec![
+			(alice, vec![(polkadot.clone(), alice.encode()), (kusama.clone(), alice.encode())]),
+			(bob, vec![(polkadot.clone(), bob.encode())]),
+			(charlie, vec![]),
+		]),
+		Ok(vec![1, 0, 2])
+	);
+
+	assert_eq!(identity.owner_of(1), Some(alice));
+	assert_eq!(
+		identity.number_to_identity.get(1).unwrap(),
+		IdentityInfo {
+			addresses: vec![(polkadot.clone(), alice.encode()), (kusama, alice.encode())]
+		}
+	);
+	// Bob's existing identity is left untouched.
+	assert_eq!(identity.number_to_identity.get(0).unwrap(), IdentityInfo::default());
+	assert_eq!(identity.owner_of(2), Some(charlie));
+
+	assert_eq!(identity.total_addresses(), 2);
+	assert_eq!(identity.chain_usage.get(&polkadot), Some(1));
+}
+
+#[ink::test]
+fn seq_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.seq_of(0), 0);
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.seq_of(0), 1);
+
+	let last_address_added_seq = |identity: &Identity| {
+		let last_event = recorded_events().last().unwrap();
+		let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+			.expect("Failed to decode event");
+
+		let Event::AddressAdded(AddressAdded { seq, .. }) = decoded_event else {
+			panic!("AddressAdded event should be emitted")
+		};
+		assert_eq!(seq, identity.seq_of(0));
+
+		seq
+	};
+
+	assert_eq!(identity.add_address(polkadot, alice.encode()), Ok(()));
+	let first_seq = last_address_added_seq(&identity);
+
+	assert_eq!(identity.add_address(kusama, alice.encode()), Ok(()));
+	assert_eq!(last_address_added_seq(&identity), first_seq + 1);
+
+	// Events of other identities don't affect the sequence.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.seq_of(0), first_seq + 1);
+	assert_eq!(identity.seq_of(1), 1);
+}
+
+#[ink::test]
+fn suggested_chains_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	for chain in [polkadot.clone(), moonbeam.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.suggested_chains(0, 5), Err(Error::IdentityDoesntExist));
+
+	// Polkadot is used by two identities, Moonbeam by one and Kusama by none.
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(moonbeam.clone(), alice.encode()), Ok(()));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.add_address(polkadot.clone(), bob.encode()), Ok(()));
+
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.create_identity(), Ok(2));
+
+	assert_eq!(identity.suggested_chains(2, 5), Ok(vec![polkadot.clone(), moonbeam.clone()]));
+	assert_eq!(identity.suggested_chains(2, 1), Ok(vec![polkadot]));
+	// Polkadot is not suggested to Bob since Bob already has an address on it.
+	assert_eq!(identity.suggested_chains(1, 5), Ok(vec![moonbeam]));
+	assert_eq!(identity.suggested_chains(0, 5), Ok(vec![]));
+}
+
+#[ink::test]
+fn complete_recovery_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, django, .. } =
+		get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.set_recovery_account(bob), Ok(()));
+
+	// Only the recovery account can complete the recovery.
+	assert_eq!(identity.complete_recovery(0, charlie, django), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.complete_recovery(1, charlie, django), Err(Error::NotAllowed));
+	assert_eq!(identity.complete_recovery(0, charlie, django), Ok(()));
+
+	assert_eq!(identity.owner_of.get(0), Some(charlie));
+	assert_eq!(identity.identity_of.get(alice), None);
+	assert_eq!(identity.identity_of.get(charlie), Some(0));
+	assert_eq!(identity.recovery_account_of.get(0), Some(django));
+
+	let events: Vec<_> = recorded_events().collect();
+	let decode = |index: usize| {
+		<Event as scale::Decode>::decode(&mut &events[index].data[..])
+			.expect("Failed to decode event")
+	};
+
+	let Event::OwnershipTransferred(OwnershipTransferred {
+		identity_no,
+		previous_owner,
+		new_owner,
+		..
+	}) = decode(events.len() - 3)
+	else {
+		panic!("OwnershipTransferred event should be emitted")
+	};
+	assert_eq!(identity_no, 0);
+	assert_eq!(previous_owner, alice);
+	assert_eq!(new_owner, charlie);
+
+	let Event::RecoveryUsed(RecoveryUsed { identity_no, recovery_account, new_owner, .. }) =
+		decode(events.len() - 2)
+	else {
+		panic!("RecoveryUsed event should be emitted")
+	};
+	assert_eq!(identity_no, 0);
+	assert_eq!(recovery_account, bob);
+	assert_eq!(new_owner, charlie);
+
+	let Event::RecoveryAccountSet(RecoveryAccountSet { identity_no, recovery_account, .. }) =
+		decode(events.len() - 1)
+	else {
+		panic!("RecoveryAccountSet event should be emitted")
+	};
+	assert_eq!(identity_no, 0);
+	assert_eq!(recovery_account, django);
+
+	// Bob is no longer the recovery account.
+	assert_eq!(identity.complete_recovery(0, bob, alice), Err(Error::NotAllowed));
+}
+
+#[ink::test]
+fn complete_recovery_rejects_self_recovery() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.set_recovery_account(bob), Ok(()));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.complete_recovery(0, charlie, charlie), Err(Error::RecoveryIsOwner));
+
+	assert_eq!(identity.owner_of.get(0), Some(alice));
+	assert_eq!(identity.recovery_account_of.get(0), Some(bob));
+}
+
+#[ink::test]
+fn max_addresses_per_identity_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	for para_id in 0..3 {
+		assert!(identity
+			.add_chain(
+				(para_id, Polkadot),
+				ChainInfo { account_type: AccountId32, ..Default::default() }
+			)
+			.is_ok());
+	}
+
+	// Only an admin can change the maximum.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.set_max_addresses_per_identity(2), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.set_max_addresses_per_identity(2), Ok(()));
+	assert_eq!(identity.limits().max_addresses_per_identity, 2);
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address((0, Polkadot), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address((1, Polkadot), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address((2, Polkadot), alice.encode()), Err(Error::TooManyAddresses));
+
+	// Removing an address frees up room for another one.
+	assert_eq!(identity.remove_address((0, Polkadot)), Ok(()));
+	assert_eq!(identity.add_address((2, Polkadot), alice.encode()), Ok(()));
+}
+
+#[ink::test]
+fn cas_set_recovery_works() {
+	let DefaultAccounts::<DefaultEnvironment> { bob, charlie, django, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.cas_set_recovery(None, bob), Err(Error::NotAllowed));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	// Stale: the identity doesn't have a recovery account yet.
+	assert_eq!(identity.cas_set_recovery(Some(charlie), bob), Err(Error::RecoveryMismatch));
+	assert_eq!(identity.recovery_account_of.get(0), None);
+
+	assert_eq!(identity.cas_set_recovery(None, bob), Ok(()));
+	assert_eq!(identity.recovery_account_of.get(0), Some(bob));
+
+	// Stale: the recovery account got changed in the meantime.
+	assert_eq!(identity.cas_set_recovery(None, charlie), Err(Error::RecoveryMismatch));
+	assert_eq!(identity.cas_set_recovery(Some(django), charlie), Err(Error::RecoveryMismatch));
+	assert_eq!(identity.recovery_account_of.get(0), Some(bob));
+
+	assert_eq!(identity.cas_set_recovery(Some(bob), charlie), Ok(()));
+	assert_eq!(identity.recovery_account_of.get(0), Some(charlie));
+}
+
+#[ink::test]
+fn creator_of_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.creator_of(0), None);
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.creator_of(0), Some(alice));
+
+	assert_eq!(identity.transfer_ownership(0, bob), Ok(()));
+	assert_eq!(identity.owner_of(0), Some(bob));
+	assert_eq!(identity.creator_of(0), Some(alice));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.remove_identity(), Ok(()));
+	assert_eq!(identity.creator_of(0), None);
+}
+
+#[ink::test]
+fn bulk_address_paths_respect_max_addresses_per_identity() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let chains: Vec<ChainId> = (0..3).map(|para_id| (para_id, Polkadot)).collect();
+	for chain in chains.iter() {
+		assert!(identity
+			.add_chain(chain.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.set_max_addresses_per_identity(2), Ok(()));
+
+	let addresses_of =
+		|address: EncryptedAddress, count: usize| -> Vec<(ChainId, EncryptedAddress)> {
+			chains
+				.iter()
+				.take(count)
+				.map(|chain| (chain.clone(), address.clone()))
+				.collect()
+		};
+
+	// Seeding above the maximum is rejected and nothing gets created.
+	assert_eq!(
+		identity.seed_identities(vec![
+			(bob, addresses_of(bob.encode(), 1)),
+			(charlie, addresses_of(charlie.encode(), 3))
+		]),
+		Err(Error::TooManyAddresses)
+	);
+	assert_eq!(identity.identity_of(bob), None);
+
+	// Seeding exactly at the maximum succeeds.
+	assert_eq!(identity.seed_identities(vec![(bob, addresses_of(bob.encode(), 2))]), Ok(vec![0]));
+	assert_eq!(identity.identity(0).unwrap().addresses.len(), 2);
+
+	// The same goes for staging addresses when transferring an identity.
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(
+		identity.transfer_ownership_with_addresses(1, charlie, addresses_of(charlie.encode(), 3)),
+		Err(Error::TooManyAddresses)
+	);
+	assert_eq!(identity.owner_of(1), Some(alice));
+	assert_eq!(
+		identity.transfer_ownership_with_addresses(1, charlie, addresses_of(charlie.encode(), 2)),
+		Ok(())
+	);
+
+	// Staged addresses can't be applied once the maximum got lowered.
+	assert_eq!(identity.set_max_addresses_per_identity(1), Ok(()));
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.apply_staged_addresses(), Err(Error::TooManyAddresses));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.set_max_addresses_per_identity(2), Ok(()));
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.apply_staged_addresses(), Ok(()));
+	assert_eq!(identity.identity(1).unwrap().addresses.len(), 2);
+}
+
+#[ink::test]
+fn identities_with_recovery_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, django, eve, .. } =
+		get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	// Django is the guardian of Alice's and Charlie's identities.
+	for (owner, recovery) in [(alice, django), (bob, eve), (charlie, django)] {
+		set_caller::<DefaultEnvironment>(owner);
+		assert!(identity.create_identity().is_ok());
+		assert_eq!(identity.set_recovery_account(recovery), Ok(()));
+	}
+
+	assert_eq!(identity.identities_with_recovery(django, 0, 3), vec![0, 2]);
+	assert_eq!(identity.identities_with_recovery(django, 1, 3), vec![2]);
+	assert_eq!(identity.identities_with_recovery(eve, 0, 10), vec![1]);
+	assert_eq!(identity.identities_with_recovery(alice, 0, 3), vec![]);
+}
+
+#[ink::test]
+fn locked_identity_rejects_changes() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.set_locked_until(5), Err(Error::NotAllowed));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.set_recovery_account(bob), Ok(()));
+
+	assert_eq!(identity.set_locked_until(3), Ok(()));
+	assert_eq!(identity.locked_until(0), Some(3));
+
+	// The lock can be extended but not shortened.
+	assert_eq!(identity.set_locked_until(2), Err(Error::IdentityLocked));
+	assert_eq!(identity.set_locked_until(4), Ok(()));
+	assert_eq!(identity.locked_until(0), Some(4));
+
+	assert_eq!(identity.add_address(kusama.clone(), alice.encode()), Err(Error::IdentityLocked));
+	assert_eq!(identity.update_address(polkadot.clone(), bob.encode()), Err(Error::IdentityLocked));
+	assert_eq!(identity.remove_address(polkadot.clone()), Err(Error::IdentityLocked));
+	assert_eq!(identity.transfer_ownership(0, bob), Err(Error::IdentityLocked));
+	assert_eq!(identity.remove_identity(), Err(Error::IdentityLocked));
+
+	// Not even the recovery account can transfer a locked identity.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.transfer_ownership(0, bob), Err(Error::IdentityLocked));
+
+	for _ in 0..4 {
+		advance_block::<DefaultEnvironment>();
+	}
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.add_address(kusama, alice.encode()), Ok(()));
+	assert_eq!(identity.update_address(polkadot, bob.encode()), Ok(()));
+
+	// Once the lock expired it can be set to an earlier block again.
+	assert_eq!(identity.set_locked_until(1), Ok(()));
+
+	assert_eq!(identity.transfer_ownership(0, bob), Ok(()));
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.remove_identity(), Ok(()));
+	assert_eq!(identity.locked_until(0), None);
+}
+
+#[ink::test]
+fn reconcile_chains_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.reconcile_chains(), Ok(0));
+
+	// Desync the registry: Kusama has no `ChainInfo` anymore and Polkadot is
+	// listed twice.
+	identity.chain_info_of.remove(kusama.clone());
+	identity.chain_ids.push(polkadot.clone());
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.reconcile_chains(), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.reconcile_chains(), Ok(2));
+	assert_eq!(identity.chain_ids, vec![polkadot]);
+
+	assert_eq!(identity.reconcile_chains(), Ok(0));
+}
+
+#[ink::test]
+fn chains_supported_works() {
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	let moonbeam: ChainId = (2004, Polkadot);
+
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(
+		identity.chains_supported(vec![kusama.clone(), moonbeam.clone(), polkadot.clone()]),
+		Ok(vec![(kusama, true), (moonbeam.clone(), false), (polkadot, true)])
+	);
+	assert_eq!(identity.chains_supported(vec![]), Ok(vec![]));
+
+	// Error: Too many chains.
+	assert_eq!(
+		identity.chains_supported(vec![moonbeam; BATCH_SIZE_LIMIT + 1]),
+		Err(Error::BatchSizeExceeded)
+	);
+}
+
+#[ink::test]
+fn receiving_preference_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.set_receiving_preference(Some(polkadot.clone())), Err(Error::NotAllowed));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.receiving_preference_of(0), None);
+
+	// Error: The identity has no address on Polkadot.
+	assert_eq!(identity.set_receiving_preference(Some(polkadot.clone())), Err(Error::InvalidChain));
+
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(kusama.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.set_receiving_preference(Some(polkadot.clone())), Ok(()));
+	assert_eq!(identity.receiving_preference_of(0), Some(polkadot.clone()));
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::ReceivingPreferenceSet(ReceivingPreferenceSet { identity_no, chain, .. }) =
+		decoded_event
+	else {
+		panic!("ReceivingPreferenceSet event should be emitted")
+	};
+	assert_eq!(identity_no, 0);
+	assert_eq!(chain, Some(polkadot.clone()));
+
+	// Removing another address keeps the preference.
+	assert_eq!(identity.remove_address(kusama), Ok(()));
+	assert_eq!(identity.receiving_preference_of(0), Some(polkadot.clone()));
+
+	// Removing the preferred address clears it.
+	assert_eq!(identity.remove_address(polkadot.clone()), Ok(()));
+	assert_eq!(identity.receiving_preference_of(0), None);
+
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.set_receiving_preference(Some(polkadot)), Ok(()));
+	assert_eq!(identity.set_receiving_preference(None), Ok(()));
+	assert_eq!(identity.receiving_preference_of(0), None);
+}
+
+#[ink::test]
+fn addresses_with_info_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+
+	let polkadot_info = ChainInfo { account_type: AccountId32, ..Default::default() };
+	let moonbeam_info = ChainInfo {
+		account_type: AccountKey20,
+		relay: Some(polkadot.clone()),
+		..Default::default()
+	};
+	assert!(identity.add_chain(polkadot.clone(), polkadot_info.clone()).is_ok());
+	assert!(identity.add_chain(moonbeam.clone(), moonbeam_info.clone()).is_ok());
+	assert!(identity.add_chain(kusama.clone(), polkadot_info.clone()).is_ok());
+
+	assert_eq!(identity.addresses_with_info(0), Err(Error::IdentityDoesntExist));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.addresses_with_info(0), Ok(vec![]));
+
+	let moonbeam_address = vec![42; 20];
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(moonbeam.clone(), moonbeam_address.clone()), Ok(()));
+	assert_eq!(identity.add_address(kusama.clone(), alice.encode()), Ok(()));
+
+	// Kusama is no longer supported.
+	assert_eq!(identity.remove_chain(kusama), Ok(()));
+
+	assert_eq!(
+		identity.addresses_with_info(0),
+		Ok(vec![
+			(polkadot, polkadot_info, alice.encode()),
+			(moonbeam, moonbeam_info, moonbeam_address)
+		])
+	);
+}
+
+#[ink::test]
+fn can_batch_add_addresses_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	assert!(identity
+		.add_chain(polkadot.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+		.is_ok());
+
+	assert_eq!(identity.can_batch_add_addresses(0, 1), Err(Error::IdentityDoesntExist));
+
+	assert_eq!(identity.set_max_addresses_per_identity(3), Ok(()));
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot, alice.encode()), Ok(()));
+
+	assert_eq!(identity.can_batch_add_addresses(0, 0), Ok(true));
+	assert_eq!(identity.can_batch_add_addresses(0, 1), Ok(true));
+	assert_eq!(identity.can_batch_add_addresses(0, 2), Ok(true));
+	assert_eq!(identity.can_batch_add_addresses(0, 3), Ok(false));
+	assert_eq!(identity.can_batch_add_addresses(0, u32::MAX), Ok(false));
+}
+
+#[ink::test]
+fn transaction_destination_follows_successor() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	let moonbeam: ChainId = (2004, Polkadot);
+	for chain in [polkadot.clone(), kusama.clone(), moonbeam.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	// Alice has an address on Kusama, Bob on Polkadot and Charlie on Moonbeam.
+	for (owner, chain) in
+		[(alice, kusama.clone()), (bob, polkadot.clone()), (charlie, moonbeam.clone())]
+	{
+		set_caller::<DefaultEnvironment>(owner);
+		assert!(identity.create_identity().is_ok());
+		assert_eq!(identity.add_address(chain, owner.encode()), Ok(()));
+	}
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.set_successor(Some(0)), Err(Error::InvalidSuccessor));
+	assert_eq!(identity.set_successor(Some(42)), Err(Error::IdentityDoesntExist));
+	assert_eq!(identity.set_successor(Some(1)), Ok(()));
+	assert_eq!(identity.successor_of(0), Some(1));
+
+	// The successor is only used when asked for.
+	assert_eq!(
+		identity.transaction_destination(0, polkadot.clone(), false),
+		Err(Error::InvalidChain)
+	);
+	assert_eq!(identity.transaction_destination(0, polkadot.clone(), true), Ok(bob.encode()));
+	// The own address takes precedence over the one of the successor.
+	assert_eq!(identity.transaction_destination(0, kusama.clone(), true), Ok(alice.encode()));
+
+	// Only one hop is followed, Charlie's address is never returned for Alice.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.set_successor(Some(2)), Ok(()));
+	assert_eq!(identity.transaction_destination(1, moonbeam.clone(), true), Ok(charlie.encode()));
+	assert_eq!(
+		identity.transaction_destination(0, moonbeam.clone(), true),
+		Err(Error::InvalidChain)
+	);
+
+	// Successors pointing at each other don't cause a cycle.
+	assert_eq!(identity.set_successor(Some(0)), Ok(()));
+	assert_eq!(
+		identity.transaction_destination(0, moonbeam.clone(), true),
+		Err(Error::InvalidChain)
+	);
+	assert_eq!(identity.transaction_destination(1, moonbeam, true), Err(Error::InvalidChain));
+	assert_eq!(identity.transaction_destination(1, kusama, true), Ok(alice.encode()));
+
+	assert_eq!(identity.set_successor(None), Ok(()));
+	assert_eq!(identity.successor_of(1), None);
+}
+
+#[ink::test]
+fn network_usage_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.network_usage(), vec![(Polkadot, 0), (Kusama, 0)]);
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	for chain in [polkadot.clone(), moonbeam.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(moonbeam.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(kusama.clone(), alice.encode()), Ok(()));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.add_address(polkadot, bob.encode()), Ok(()));
+
+	assert_eq!(identity.network_usage(), vec![(Polkadot, 3), (Kusama, 1)]);
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.remove_address(kusama), Ok(()));
+	assert_eq!(identity.network_usage(), vec![(Polkadot, 3), (Kusama, 0)]);
+}
+
+#[ink::test]
+fn validate_chain_set_works() {
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+
+	let relay_chain = ChainInfo { account_type: AccountId32, ..Default::default() };
+	let parachain = ChainInfo {
+		account_type: AccountKey20,
+		relay: Some(polkadot.clone()),
+		..Default::default()
+	};
+
+	assert_eq!(
+		Identity::validate_chain_set(
+			&[relay_chain.clone(), parachain.clone()],
+			&[polkadot.clone(), moonbeam.clone()]
+		),
+		Ok(())
+	);
+	assert_eq!(Identity::validate_chain_set(&[], &[]), Ok(()));
+
+	// Length mismatch.
+	assert_eq!(
+		Identity::validate_chain_set(
+			&[relay_chain.clone(), parachain.clone()],
+			&[polkadot.clone(), moonbeam.clone(), (0, Kusama)]
+		),
+		Err(Error::ChainCountMismatch)
+	);
+
+	// Duplicate chain ids.
+	assert_eq!(
+		Identity::validate_chain_set(
+			&[relay_chain.clone(), relay_chain.clone()],
+			&[polkadot.clone(), polkadot.clone()]
+		),
+		Err(Error::DuplicateChain)
+	);
+
+	// The relay chain isn't part of the set.
+	assert_eq!(
+		Identity::validate_chain_set(
+			&[parachain.clone(), relay_chain.clone()],
+			&[moonbeam.clone(), (0, Kusama)]
+		),
+		Err(Error::InvalidChain)
+	);
+
+	// The chain is its own relay chain.
+	assert_eq!(Identity::validate_chain_set(&[parachain], &[polkadot]), Err(Error::InvalidChain));
+}
+
+#[ink::test]
+#[should_panic(expected = "Invalid chain set: DuplicateChain")]
+fn init_with_chains_rejects_invalid_chain_set() {
+	let chain = ChainInfo { account_type: AccountId32, ..Default::default() };
+
+	Identity::init_with_chains(vec![chain.clone(), chain], vec![(0, Polkadot), (0, Polkadot)]);
+}
+
+#[ink::test]
+fn addresses_of_paged_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let chains: Vec<ChainId> = (0..4).map(|para_id| (para_id, Polkadot)).collect();
+	for chain in chains.iter() {
+		assert!(identity
+			.add_chain(chain.clone(), ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.addresses_of_paged(0, 0, 10), Err(Error::IdentityDoesntExist));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	for chain in chains.iter() {
+		assert_eq!(identity.add_address(chain.clone(), alice.encode()), Ok(()));
+	}
+
+	assert_eq!(
+		identity.addresses_of_paged(0, 1, 2),
+		Ok(vec![(chains[1].clone(), alice.encode()), (chains[2].clone(), alice.encode())])
+	);
+	assert_eq!(
+		identity.addresses_of_paged(0, 3, 10),
+		Ok(vec![(chains[3].clone(), alice.encode())])
+	);
+	assert_eq!(identity.addresses_of_paged(0, 0, u32::MAX).map(|page| page.len()), Ok(4));
+
+	// Out of range.
+	assert_eq!(identity.addresses_of_paged(0, 4, 10), Ok(vec![]));
+	assert_eq!(identity.addresses_of_paged(0, u32::MAX, 10), Ok(vec![]));
+}
+
+#[ink::test]
+fn subscribers_work() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.subscribers(), vec![]);
+
+	// Only an admin can manage the subscribers.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.add_subscriber(charlie), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.add_subscriber(charlie), Ok(()));
+	assert_eq!(identity.add_subscriber(charlie), Err(Error::AlreadySubscribed));
+	assert_eq!(identity.subscribers(), vec![charlie]);
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.remove_subscriber(charlie), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.remove_subscriber(bob), Err(Error::NotSubscribed));
+	assert_eq!(identity.remove_subscriber(charlie), Ok(()));
+	assert_eq!(identity.subscribers(), vec![]);
+
+	for _ in 0..SUBSCRIBER_LIMIT {
+		identity.subscribers.push(bob);
+	}
+	assert_eq!(identity.add_subscriber(charlie), Err(Error::TooManySubscribers));
+}
+
+#[ink::test]
+fn is_identity_clean_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.is_identity_clean(0), Err(Error::IdentityDoesntExist));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.is_identity_clean(0), Ok(true));
+
+	assert_eq!(identity.add_address(polkadot, alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(kusama.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.is_identity_clean(0), Ok(true));
+
+	assert_eq!(identity.remove_chain(kusama), Ok(()));
+	assert_eq!(identity.is_identity_clean(0), Ok(false));
+
+	assert!(identity.prune_stale_addresses().is_ok());
+	assert_eq!(identity.is_identity_clean(0), Ok(true));
+}
+
+#[ink::test]
+fn admin_config_works() {
+	let mut identity = Identity::new();
+
+	assert_eq!(
+		identity.admin_config(),
+		AdminConfig {
+			address_size_limit: ADDRESS_SIZE_LIMIT as u32,
+			max_addresses_per_identity: DEFAULT_MAX_ADDRESSES_PER_IDENTITY,
+			require_recovery: false,
+			gated: false,
+			chain_removal_delay: 0,
+		}
+	);
+
+	assert_eq!(identity.set_gated(true), Ok(()));
+	assert_eq!(identity.set_require_recovery(true), Ok(()));
+	assert_eq!(identity.set_max_addresses_per_identity(10), Ok(()));
+	assert_eq!(identity.set_chain_removal_delay(100), Ok(()));
+
+	assert_eq!(
+		identity.admin_config(),
+		AdminConfig {
+			address_size_limit: ADDRESS_SIZE_LIMIT as u32,
+			max_addresses_per_identity: 10,
+			require_recovery: true,
+			gated: true,
+			chain_removal_delay: 100,
+		}
+	);
+}
+
+#[ink::test]
+fn transfer_many_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, django, eve, frank, .. } =
+		get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	// Alice owns identity 0 and is the recovery account of Bob's identity.
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.set_recovery_account(alice), Ok(()));
+
+	set_caller::<DefaultEnvironment>(alice);
+	let events_before = recorded_events().count();
+	assert_eq!(identity.transfer_many(vec![(0, django), (1, eve)]), Ok(()));
+
+	assert_eq!(identity.owner_of(0), Some(django));
+	assert_eq!(identity.owner_of(1), Some(eve));
+	assert_eq!(identity.identity_of(alice), None);
+	assert_eq!(identity.identity_of(bob), None);
+
+	// Only the transfer done as the recovery account emits `RecoveryUsed`.
+	let events: Vec<_> = recorded_events().skip(events_before).collect();
+	assert_eq!(events.len(), 3);
+	let decode = |index: usize| {
+		<Event as scale::Decode>::decode(&mut &events[index].data[..])
+			.expect("Failed to decode event")
+	};
+
+	let Event::OwnershipTransferred(OwnershipTransferred {
+		identity_no,
+		previous_owner,
+		new_owner,
+		..
+	}) = decode(0)
+	else {
+		panic!("OwnershipTransferred event should be emitted")
+	};
+	assert_eq!((identity_no, previous_owner, new_owner), (0, alice, django));
+
+	let Event::OwnershipTransferred(OwnershipTransferred {
+		identity_no,
+		previous_owner,
+		new_owner,
+		..
+	}) = decode(1)
+	else {
+		panic!("OwnershipTransferred event should be emitted")
+	};
+	assert_eq!((identity_no, previous_owner, new_owner), (1, bob, eve));
+
+	let Event::RecoveryUsed(RecoveryUsed { identity_no, recovery_account, new_owner, .. }) =
+		decode(2)
+	else {
+		panic!("RecoveryUsed event should be emitted")
+	};
+	assert_eq!((identity_no, recovery_account, new_owner), (1, alice, eve));
+
+	// Error: Frank isn't allowed to transfer any of the identities.
+	set_caller::<DefaultEnvironment>(frank);
+	assert_eq!(identity.transfer_many(vec![(0, bob)]), Err(Error::NotAllowed));
+
+	// Error: Too many transfers.
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(
+		identity.transfer_many(vec![(0, bob); BATCH_SIZE_LIMIT + 1]),
+		Err(Error::BatchSizeExceeded)
+	);
+}
+
+#[ink::test]
+fn transfer_many_rolls_back_on_taken_target() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, django, eve, .. } =
+		get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	for owner in [bob, charlie, django] {
+		set_caller::<DefaultEnvironment>(owner);
+		assert!(identity.create_identity().is_ok());
+		assert_eq!(identity.set_recovery_account(alice), Ok(()));
+	}
+
+	set_caller::<DefaultEnvironment>(alice);
+	let events_before = recorded_events().count();
+
+	// Error: Django already owns an identity, so none of the identities are
+	// transferred.
+	assert_eq!(
+		identity.transfer_many(vec![(0, eve), (1, django)]),
+		Err(Error::AlreadyIdentityOwner)
+	);
+	// Error: Both identities can't be transferred to the same account.
+	assert_eq!(identity.transfer_many(vec![(0, eve), (1, eve)]), Err(Error::AlreadyIdentityOwner));
+	// Error: The same identity can't be transferred twice.
+	assert_eq!(identity.transfer_many(vec![(0, eve), (0, alice)]), Err(Error::NotAllowed));
+
+	assert_eq!(identity.owner_of(0), Some(bob));
+	assert_eq!(identity.owner_of(1), Some(charlie));
+	assert_eq!(identity.identity_of(eve), None);
+	assert_eq!(recorded_events().count(), events_before);
+}
+
+#[ink::test]
+fn address_count_histogram_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonbeam: ChainId = (2004, Polkadot);
+	for chain in [polkadot.clone(), moonbeam.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	// Alice has no addresses, Bob has one and Charlie has two.
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.add_address(polkadot.clone(), bob.encode()), Ok(()));
+
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.create_identity(), Ok(2));
+	assert_eq!(identity.add_address(polkadot, charlie.encode()), Ok(()));
+	assert_eq!(identity.add_address(moonbeam, alice.encode()), Ok(()));
+
+	let histogram = identity.address_count_histogram(0, 3);
+	assert_eq!(histogram.len(), HISTOGRAM_BUCKETS);
+	assert_eq!(histogram[..3], [1, 1, 1]);
+	assert_eq!(histogram.iter().sum::<u32>(), 3);
+
+	// Only the identities within the range are counted.
+	let histogram = identity.address_count_histogram(1, 3);
+	assert_eq!(histogram[..3], [0, 1, 1]);
+
+	// Identities that don't exist aren't counted.
+	assert_eq!(identity.address_count_histogram(3, 10).iter().sum::<u32>(), 0);
+}
+
+#[ink::test]
+fn addresses_packed_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.addresses_packed(0), Err(Error::IdentityDoesntExist));
+
+	let polkadot: ChainId = (0, Polkadot);
+	let moonriver: ChainId = (2023, Kusama);
+	for chain in [polkadot.clone(), moonriver.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.addresses_packed(0), Ok(vec![]));
+
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(moonriver.clone(), vec![1, 2, 3]), Ok(()));
+
+	let packed = identity.addresses_packed(0).unwrap();
+
+	// Decode the packed entries back.
+	let mut input = &packed[..];
+	let mut entries: Vec<(ChainId, EncryptedAddress)> = vec![];
+	while !input.is_empty() {
+		let chain = <ChainId as scale::Decode>::decode(&mut input).unwrap();
+		let len = <u32 as scale::Decode>::decode(&mut input).unwrap() as usize;
+		let (address, rest) = input.split_at(len);
+		entries.push((chain, address.to_vec()));
+		input = rest;
+	}
+
+	assert_eq!(entries, vec![(polkadot, alice.encode()), (moonriver, vec![1, 2, 3])]);
+
+	// The layout is fixed: chain id, network index, address length, address.
+	assert_eq!(packed[packed.len() - 12..], [0xe7, 0x07, 0, 0, 1, 3, 0, 0, 0, 1, 2, 3]);
+}
+
+#[ink::test]
+fn rotate_owner_key_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	// Error: Alice doesn't own an identity yet.
+	assert_eq!(identity.rotate_owner_key(bob), Err(Error::NotAllowed));
+
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.create_identity(), Ok(1));
+
+	// Error: Charlie already owns an identity.
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.rotate_owner_key(charlie), Err(Error::AlreadyIdentityOwner));
+
+	assert_eq!(identity.rotate_owner_key(bob), Ok(()));
+	assert_eq!(identity.owner_of(0), Some(bob));
+	assert_eq!(identity.identity_of(alice), None);
+	assert_eq!(identity.identity_of(bob), Some(0));
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::OwnershipTransferred(OwnershipTransferred {
+		identity_no,
+		previous_owner,
+		new_owner,
+		..
+	}) = decoded_event
+	else {
+		panic!("OwnershipTransferred event should be emitted")
+	};
+	assert_eq!((identity_no, previous_owner, new_owner), (0, alice, bob));
+
+	// Error: Only the previous owner can roll back.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.rollback_owner(), Err(Error::NoRotation));
+}
+
+#[ink::test]
+fn rollback_owner_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.rotate_owner_key(bob), Ok(()));
+
+	// Bob tries to prevent the rollback by locking the identity.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.set_locked_until(1_000), Ok(()));
+
+	advance_block::<DefaultEnvironment>();
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.rollback_owner(), Ok(()));
+	assert_eq!(identity.owner_of(0), Some(alice));
+	assert_eq!(identity.identity_of(bob), None);
+	assert_eq!(identity.identity_of(alice), Some(0));
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::OwnershipTransferred(OwnershipTransferred { previous_owner, new_owner, .. }) =
+		decoded_event
+	else {
+		panic!("OwnershipTransferred event should be emitted")
+	};
+	assert_eq!((previous_owner, new_owner), (bob, alice));
+
+	// Error: The rotation can only be rolled back once.
+	assert_eq!(identity.rollback_owner(), Err(Error::NoRotation));
+}
+
+#[ink::test]
+fn rollback_owner_after_window_fails() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.rotate_owner_key(bob), Ok(()));
+
+	for _ in 0..=ROTATION_ROLLBACK_WINDOW {
+		advance_block::<DefaultEnvironment>();
+	}
+
+	assert_eq!(identity.rollback_owner(), Err(Error::RotationExpired));
+	assert_eq!(identity.owner_of(0), Some(bob));
+	assert_eq!(identity.identity_of(alice), None);
+}
+
+#[ink::test]
+fn swap_addresses_swaps_memos() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	let moonbeam: ChainId = (2004, Polkadot);
+	for chain in [polkadot.clone(), kusama.clone(), moonbeam.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(
+		identity.add_address_with_memo(polkadot.clone(), alice.encode(), Some(vec![42])),
+		Ok(())
+	);
+	assert_eq!(identity.add_address(kusama.clone(), bob.encode()), Ok(()));
+	assert_eq!(
+		identity.add_address_with_memo(moonbeam.clone(), bob.encode(), Some(vec![7])),
+		Ok(())
+	);
+
+	// The memo moves along with the address.
+	assert_eq!(identity.swap_addresses(polkadot.clone(), kusama.clone()), Ok(()));
+	assert_eq!(identity.memo_of(0, polkadot.clone()), None);
+	assert_eq!(identity.memo_of(0, kusama.clone()), Some(vec![42]));
+
+	// Both memos get swapped.
+	assert_eq!(identity.swap_addresses(kusama.clone(), moonbeam.clone()), Ok(()));
+	assert_eq!(identity.memo_of(0, kusama), Some(vec![7]));
+	assert_eq!(identity.memo_of(0, moonbeam), Some(vec![42]));
+}
+
+#[ink::test]
+fn proofs_get_cleared_when_addresses_change() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let kusama: ChainId = (0, Kusama);
+	for chain in [polkadot.clone(), kusama.clone()] {
+		assert!(identity
+			.add_chain(chain, ChainInfo { account_type: AccountId32, ..Default::default() })
+			.is_ok());
+	}
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.add_address(polkadot.clone(), alice.encode()), Ok(()));
+	assert_eq!(identity.add_address(kusama.clone(), bob.encode()), Ok(()));
+	assert_eq!(identity.set_proof(polkadot.clone(), vec![1; 64]), Ok(()));
+	assert_eq!(identity.set_proof(kusama.clone(), vec![2; 64]), Ok(()));
+
+	// Swapping the addresses clears the proofs of both chains.
+	assert_eq!(identity.swap_addresses(polkadot.clone(), kusama.clone()), Ok(()));
+	assert_eq!(identity.proof_of(0, polkadot.clone()), None);
+	assert_eq!(identity.proof_of(0, kusama.clone()), None);
+
+	assert_eq!(identity.set_proof(polkadot.clone(), vec![1; 64]), Ok(()));
+	assert_eq!(identity.set_proof(kusama.clone(), vec![2; 64]), Ok(()));
+
+	// Updating an address clears only the proof of its chain.
+	assert_eq!(identity.update_address(polkadot.clone(), charlie.encode()), Ok(()));
+	assert_eq!(identity.proof_of(0, polkadot), None);
+	assert_eq!(identity.proof_of(0, kusama), Some(vec![2; 64]));
+}
+
+#[ink::test]
+fn finalize_rotation_works() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.create_identity(), Ok(0));
+
+	// Error: There is no rotation to finalize.
+	assert_eq!(identity.finalize_rotation(), Err(Error::NoRotation));
+
+	assert_eq!(identity.rotate_owner_key(bob), Ok(()));
+
+	// Error: Alice no longer owns the identity.
+	assert_eq!(identity.finalize_rotation(), Err(Error::NotAllowed));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.finalize_rotation(), Ok(()));
+	assert_eq!(identity.rotation_of.get(alice), None);
+	assert_eq!(identity.rotated_from.get(0), None);
+
+	let last_event = recorded_events().last().unwrap();
+	let decoded_event = <Event as scale::Decode>::decode(&mut &last_event.data[..])
+		.expect("Failed to decode event");
+
+	let Event::RotationFinalized(RotationFinalized { identity_no, .. }) = decoded_event else {
+		panic!("RotationFinalized event should be emitted")
+	};
+	assert_eq!(identity_no, 0);
+
+	// Error: Alice can no longer roll back the rotation.
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.rollback_owner(), Err(Error::NoRotation));
+	assert_eq!(identity.owner_of(0), Some(bob));
+}
+
+#[ink::test]
+fn rotation_gets_cleared_when_identity_moves_on() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, charlie, django, .. } =
+		get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	assert_eq!(identity.create_identity(), Ok(0));
+	assert_eq!(identity.rotate_owner_key(bob), Ok(()));
+
+	// Bob transfers the identity to Charlie, so Alice's rotation is gone.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.transfer_ownership(0, charlie), Ok(()));
+	assert_eq!(identity.rotation_of.get(alice), None);
+	assert_eq!(identity.rotated_from.get(0), None);
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.rollback_owner(), Err(Error::NoRotation));
+
+	// Charlie rotates to Django, but later creates a new identity which
+	// discards the rotation.
+	set_caller::<DefaultEnvironment>(charlie);
+	assert_eq!(identity.rotate_owner_key(django), Ok(()));
+	assert_eq!(identity.create_identity(), Ok(1));
+	assert_eq!(identity.rotation_of.get(charlie), None);
+	assert_eq!(identity.rotated_from.get(0), None);
+
+	// Removing an identity discards its rotation as well.
+	set_caller::<DefaultEnvironment>(django);
+	assert_eq!(identity.rotate_owner_key(bob), Ok(()));
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.remove_identity(), Ok(()));
+	assert_eq!(identity.rotation_of.get(django), None);
+	assert_eq!(identity.rotated_from.get(0), None);
+}
+
+#[ink::test]
+fn add_chain_keeps_chain_set_valid() {
+	let mut identity = Identity::new();
+
+	let info = ChainInfo { account_type: AccountId32, ..Default::default() };
+	assert_eq!(identity.add_chain((0, Kusama), info.clone()), Ok(()));
+
+	// Error: The chain is already added.
+	assert_eq!(identity.add_chain((0, Kusama), info.clone()), Err(Error::DuplicateChain));
+	assert_eq!(identity.chain_ids, vec![(0, Kusama)]);
+
+	// Error: A chain can't be its own relay chain.
+	assert_eq!(
+		identity
+			.add_chain((2004, Kusama), ChainInfo { relay: Some((2004, Kusama)), ..info.clone() }),
+		Err(Error::InvalidChain)
+	);
+	// Error: The relay chain isn't supported.
+	assert_eq!(
+		identity
+			.add_chain((2004, Kusama), ChainInfo { relay: Some((0, Polkadot)), ..info.clone() }),
+		Err(Error::InvalidChain)
+	);
+
+	assert_eq!(
+		identity.add_chain((2004, Kusama), ChainInfo { relay: Some((0, Kusama)), ..info }),
+		Ok(())
+	);
+	assert_eq!(identity.chain_ids, vec![(0, Kusama), (2004, Kusama)]);
+}
+
+#[ink::test]
+fn claim_identity_respects_gate() {
+	let DefaultAccounts::<DefaultEnvironment> { alice, bob, .. } = get_default_accounts();
+
+	let mut identity = Identity::new();
+
+	let claim_code = b"secret claim code".to_vec();
+	let mut claim_code_hash = <Blake2x256 as HashOutput>::Type::default();
+	Blake2x256::hash(&claim_code, &mut claim_code_hash);
+
+	assert_eq!(identity.register_claimable(claim_code_hash), Ok(0));
+	assert_eq!(identity.set_gated(true), Ok(()));
+
+	// Error: Bob isn't allowlisted.
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.claim_identity(0, claim_code.clone()), Err(Error::NotAllowlisted));
+	assert_eq!(identity.owner_of(0), None);
+
+	set_caller::<DefaultEnvironment>(alice);
+	assert_eq!(identity.allow(bob), Ok(()));
+
+	set_caller::<DefaultEnvironment>(bob);
+	assert_eq!(identity.claim_identity(0, claim_code), Ok(()));
+	assert_eq!(identity.owner_of(0), Some(bob));
+}
+
+#[ink::test]
+fn relay_chain_with_parachains_cannot_be_removed() {
+	let mut identity = Identity::new();
+
+	let polkadot: ChainId = (0, Polkadot);
+	let parachain = ChainInfo { relay: Some(polkadot.clone()), ..Default::default() };
+
+	assert_eq!(identity.add_chain(polkadot.clone(), ChainInfo::default()), Ok(()));
+	assert_eq!(identity.add_chain((2000, Polkadot), parachain), Ok(()));
+
+	// Error: The parachain still points to the relay chain.
+	assert_eq!(identity.remove_chain(polkadot.clone()), Err(Error::ChainHasParachains));
+	assert_eq!(identity.schedule_chain_removal(polkadot.clone()), Err(Error::ChainHasParachains));
+	assert!(identity.chain_info_of(polkadot.clone()).is_some());
+
+	// Other chains can still be added.
+	assert_eq!(identity.add_chain((0, Kusama), ChainInfo::default()), Ok(()));
+
+	// Parachains that got added after scheduling the removal also block it.
+	assert_eq!(identity.schedule_chain_removal((0, Kusama)), Ok(()));
+	assert_eq!(
+		identity.add_chain(
+			(2023, Kusama),
+			ChainInfo { relay: Some((0, Kusama)), ..Default::default() }
+		),
+		Ok(())
+	);
+	assert_eq!(identity.execute_chain_removal((0, Kusama)), Err(Error::ChainHasParachains));
+
+	// Once the parachain is detached the relay chain can be removed.
+	assert_eq!(identity.update_chain((2000, Polkadot), None, Some(None)), Ok(()));
+	assert_eq!(identity.remove_chain(polkadot.clone()), Ok(()));
+	assert!(identity.chain_info_of(polkadot).is_none());
+}
+
+fn get_default_accounts() -> DefaultAccounts<DefaultEnvironment> {
+	default_accounts::<DefaultEnvironment>()
+}
+
+/// Computes the topic that ink! emits for an event field marked with
+/// `#[ink(topic)]`.
+fn encoded_topic(prefix: &[u8], value: impl Encode) -> Vec<u8> {
+	let mut encoded = prefix.encode();
+	value.encode_to(&mut encoded);
+
+	let mut hash = <Blake2x256 as HashOutput>::Type::default();
+	<Blake2x256 as CryptoHash>::hash(&encoded, &mut hash);
+
+	hash.to_vec()
 }
@@ -2,30 +2,100 @@
 
 use crate::{ensure, Error, ADDRESS_SIZE_LIMIT};
 use common::types::*;
-use ink::prelude::vec::Vec;
+use ink::{prelude::vec::Vec, primitives::AccountId};
 
 #[cfg(feature = "std")]
 use ink::storage::traits::StorageLayout;
 
+/// A proof that the owner of an identity controls one of its addresses,
+/// usually a signature. The contract treats it as an opaque blob.
+pub type AddressProof = Vec<u8>;
+
+/// The destination address of a receiver, or the reason it couldn't be
+/// resolved.
+pub type Destination = (IdentityNo, Result<EncryptedAddress, Error>);
+
+/// The block number type of the environment the contract runs in.
+pub type BlockNumber = <ink::env::DefaultEnvironment as ink::env::Environment>::BlockNumber;
+
+/// A memo that needs to be attached to transfers to an address, e.g. a
+/// destination tag.
+pub type Memo = Vec<u8>;
+
+/// The bounds the identity contract enforces on its inputs.
+#[derive(scale::Encode, scale::Decode, Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Limits {
+	/// The maximum size of an encrypted address.
+	pub address_size_limit: u32,
+	/// The maximum size of a proof of address control.
+	pub proof_size_limit: u32,
+	/// The maximum size of a memo.
+	pub memo_size_limit: u32,
+	/// The maximum number of items that can be passed to a batched message.
+	pub batch_size_limit: u32,
+	/// The maximum number of items a single query returns.
+	pub query_result_limit: u32,
+	/// The maximum number of addresses a single identity can have.
+	pub max_addresses_per_identity: u32,
+}
+
+/// The values of the identity contract that can be tuned by an admin.
+#[derive(scale::Encode, scale::Decode, Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct AdminConfig {
+	/// The maximum size of an encrypted address.
+	pub address_size_limit: u32,
+	/// The maximum number of addresses a single identity can have.
+	pub max_addresses_per_identity: u32,
+	/// Whether identities need a recovery account before adding addresses.
+	pub require_recovery: bool,
+	/// Whether only allowlisted accounts can create identities.
+	pub gated: bool,
+	/// The number of blocks between scheduling and executing the removal of
+	/// a chain.
+	pub chain_removal_delay: u32,
+}
+
+/// A rotation of an owner key that can still be rolled back.
+#[derive(scale::Encode, scale::Decode, Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct KeyRotation {
+	/// The identity that got rotated.
+	pub(crate) identity_no: IdentityNo,
+	/// The account the identity got rotated to.
+	pub(crate) rotated_to: AccountId,
+	/// The last block at which the rotation can be rolled back.
+	pub(crate) rollback_until: BlockNumber,
+}
+
 #[derive(scale::Encode, scale::Decode, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
 pub struct IdentityInfo {
 	/// Each address is associated with a specific blockchain.
+	///
+	/// The addresses are kept sorted by `ChainId` so that the encoding of an
+	/// identity doesn't depend on the order in which the addresses were added.
 	pub(crate) addresses: Vec<(ChainId, EncryptedAddress)>,
 }
 
 impl IdentityInfo {
+	/// Returns the address of the given chain
+	pub fn address_of(&self, chain: &ChainId) -> Option<&EncryptedAddress> {
+		self.position_of(chain).ok().map(|position| &self.addresses[position].1)
+	}
+
 	/// Adds an address for the given chain
 	pub fn add_address(&mut self, chain: ChainId, address: EncryptedAddress) -> Result<(), Error> {
 		ensure!(address.len() <= ADDRESS_SIZE_LIMIT, Error::AddressSizeExceeded);
 
-		ensure!(
-			!self.addresses.clone().into_iter().any(|address| address.0 == chain),
-			Error::AddressAlreadyAdded
-		);
-		self.addresses.push((chain, address));
-
-		Ok(())
+		match self.position_of(&chain) {
+			Ok(_) => Err(Error::AddressAlreadyAdded),
+			Err(position) => {
+				self.addresses.insert(position, (chain, address));
+				Ok(())
+			},
+		}
 	}
 
 	/// Updates the address of the given chain
@@ -36,27 +106,35 @@ impl IdentityInfo {
 	) -> Result<(), Error> {
 		ensure!(new_address.len() <= ADDRESS_SIZE_LIMIT, Error::AddressSizeExceeded);
 
-		if let Some(position) =
-			self.addresses.clone().into_iter().position(|address| address.0 == chain)
-		{
-			self.addresses[position] = (chain, new_address);
-			Ok(())
-		} else {
-			Err(Error::InvalidChain)
-		}
+		let position = self.position_of(&chain).map_err(|_| Error::InvalidChain)?;
+		self.addresses[position] = (chain, new_address);
+
+		Ok(())
+	}
+
+	/// Swaps the addresses stored on the two given chains.
+	pub fn swap_addresses(&mut self, chain_a: &ChainId, chain_b: &ChainId) -> Result<(), Error> {
+		let position_a = self.position_of(chain_a).map_err(|_| Error::InvalidChain)?;
+		let position_b = self.position_of(chain_b).map_err(|_| Error::InvalidChain)?;
+
+		let address_a = self.addresses[position_a].1.clone();
+		self.addresses[position_a].1 = self.addresses[position_b].1.clone();
+		self.addresses[position_b].1 = address_a;
+
+		Ok(())
 	}
 
 	/// Remove an address record by chain
 	pub fn remove_address(&mut self, chain: ChainId) -> Result<(), Error> {
-		let old_count = self.addresses.len();
-		self.addresses.retain(|(net, _)| *net != chain);
+		let position = self.position_of(&chain).map_err(|_| Error::InvalidChain)?;
+		self.addresses.remove(position);
 
-		let new_count = self.addresses.len();
+		Ok(())
+	}
 
-		if old_count == new_count {
-			Err(Error::InvalidChain)
-		} else {
-			Ok(())
-		}
+	/// Returns the index of the address of the given chain, or the index at
+	/// which it would need to be inserted to keep the addresses sorted.
+	fn position_of(&self, chain: &ChainId) -> Result<usize, usize> {
+		self.addresses.binary_search_by(|(id, _)| id.cmp(chain))
 	}
 }